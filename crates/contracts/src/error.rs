@@ -6,9 +6,18 @@ pub enum Error {
     #[error("Invalid Data")]
     InvalidData,
 
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
     #[error("Hex Error")]
     HexError(#[from] hex::FromHexError),
 
+    #[error("Json Error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("IO Error: {0}")]
+    IoError(#[from] std::io::Error),
+
     #[error("RPC Error")]
     RpcError(#[from] rpc::Error),
 }
@@ -0,0 +1,352 @@
+use std::sync::Arc;
+use ethabi::CodecCache;
+use serde::{Serialize, Deserialize};
+use serde::ser::SerializeMap;
+use crate::Error;
+use crate::eth::signature::{is_identifier, split_types};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMutability {
+    Pure,
+    View,
+    #[default]
+    NonPayable,
+    Payable,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AbiParam {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<AbiParam>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed: Option<bool>,
+    #[serde(rename = "internalType", default, skip_serializing_if = "Option::is_none")]
+    pub internal_type: Option<String>,
+}
+
+impl AbiParam {
+    pub fn from_type(name: &str, canonical: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidSignature(canonical.to_string());
+
+        let (kind, components) = match canonical.strip_prefix('(') {
+            Some(rest) => {
+                let close = rest.rfind(')').ok_or_else(invalid)?;
+                let components = split_types(&rest[..close]).ok_or_else(invalid)?.into_iter()
+                    .map(|component| AbiParam::from_type("", component))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (format!("tuple{}", &rest[close + 1..]), components)
+            }
+            None => (canonical.to_string(), Vec::new()),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            components,
+            indexed: None,
+            internal_type: None,
+        })
+    }
+
+    pub fn canonical_type(&self) -> String {
+        match self.kind.strip_prefix("tuple") {
+            Some(array) => {
+                let components = self.components.iter()
+                    .map(|component| component.canonical_type())
+                    .collect::<Vec<_>>();
+                format!("({}){}", components.join(","), array)
+            }
+            None => self.kind.clone(),
+        }
+    }
+
+    pub fn is_tuple(&self) -> bool {
+        self.kind.starts_with("tuple")
+    }
+}
+
+// Parses a human readable parameter such as `address indexed from` or `(uint256,bytes)[] orders`
+// into its type, name and whether it is marked as indexed.
+fn split_param(param: &str) -> (String, &str, bool) {
+    let mut tokens = param.split_whitespace().collect::<Vec<_>>();
+
+    let mut name = "";
+    if tokens.len() > 1 && tokens.last().is_some_and(|&token| token != "indexed" && is_identifier(token)) {
+        name = tokens.pop().unwrap();
+    }
+
+    let indexed = tokens.len() > 1 && tokens.last() == Some(&"indexed");
+    if indexed {
+        tokens.pop();
+    }
+
+    (tokens.join(" "), name, indexed)
+}
+
+pub(crate) fn parse_params(params: &[&str], indexable: bool) -> Result<(Vec<AbiParam>, Arc<dyn ethabi::Codec>), Error> {
+    let split = params.iter().map(|param| split_param(param)).collect::<Vec<_>>();
+    if !indexable && split.iter().any(|(_, _, indexed)| *indexed) {
+        return Err(Error::InvalidSignature(params.join(",")));
+    }
+
+    let types = split.iter().map(|(kind, _, _)| kind.as_str()).collect::<Vec<_>>();
+    let codec = CodecCache::global().parse(&types)?;
+
+    let tuple = codec.name();
+    let canonical = split_types(&tuple[1..tuple.len() - 1]).expect("codec names are well formed");
+
+    let params = split.iter().zip(canonical)
+        .map(|((_, name, indexed), canonical)| {
+            let mut param = AbiParam::from_type(name, canonical)?;
+            param.indexed = indexable.then_some(*indexed);
+            Ok(param)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok((params, codec))
+}
+
+// Params read from a JSON ABI, retyped as the parsed codec spells them so that aliases such as
+// `uint` and `byte` give the same signature as their canonical types.
+pub(crate) fn canonical_params(params: &[AbiParam]) -> Result<(Vec<AbiParam>, Arc<dyn ethabi::Codec>), Error> {
+    let codec = params_codec(params)?;
+    let tuple = codec.name();
+    let canonical = split_types(&tuple[1..tuple.len() - 1]).expect("codec names are well formed");
+
+    let params = params.iter().zip(canonical)
+        .map(|(param, canonical)| Ok(retyped(param, AbiParam::from_type(&param.name, canonical)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok((params, codec))
+}
+
+fn retyped(param: &AbiParam, canonical: AbiParam) -> AbiParam {
+    AbiParam {
+        kind: canonical.kind,
+        components: param.components.iter().zip(canonical.components)
+            .map(|(component, canonical)| retyped(component, canonical))
+            .collect(),
+        ..param.clone()
+    }
+}
+
+pub(crate) fn canonical_types(params: &[AbiParam]) -> Vec<String> {
+    params.iter().map(AbiParam::canonical_type).collect()
+}
+
+pub(crate) fn params_codec(params: &[AbiParam]) -> Result<Arc<dyn ethabi::Codec>, Error> {
+    let types = canonical_types(params);
+    Ok(CodecCache::global().parse(&types.iter().map(String::as_str).collect::<Vec<_>>())?)
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct AbiItem {
+    #[serde(rename = "type", default = "default_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<AbiParam>,
+    #[serde(default)]
+    pub outputs: Vec<AbiParam>,
+    #[serde(rename = "stateMutability", default)]
+    pub state_mutability: Option<StateMutability>,
+    #[serde(default)]
+    pub anonymous: Option<bool>,
+    #[serde(default)]
+    constant: Option<bool>,
+    #[serde(default)]
+    payable: Option<bool>,
+}
+
+impl AbiItem {
+    pub fn new(kind: &str, name: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            state_mutability: None,
+            anonymous: None,
+            constant: None,
+            payable: None,
+        }
+    }
+
+    pub fn mutability(&self) -> StateMutability {
+        match (self.state_mutability, self.constant, self.payable) {
+            (Some(state_mutability), _, _) => state_mutability,
+            (None, Some(true), _) => StateMutability::View,
+            (None, _, Some(true)) => StateMutability::Payable,
+            _ if self.kind == "receive" => StateMutability::Payable,
+            _ => StateMutability::NonPayable,
+        }
+    }
+}
+
+impl Serialize for AbiItem {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", &self.kind)?;
+
+        let kind = self.kind.as_str();
+        if matches!(kind, "function" | "event" | "error") {
+            map.serialize_entry("name", &self.name)?;
+        }
+        if matches!(kind, "function" | "event" | "error" | "constructor") {
+            map.serialize_entry("inputs", &self.inputs)?;
+        }
+        if kind == "function" {
+            map.serialize_entry("outputs", &self.outputs)?;
+        }
+        if matches!(kind, "function" | "constructor" | "fallback" | "receive") {
+            map.serialize_entry("stateMutability", &self.mutability())?;
+        }
+        if kind == "event" {
+            map.serialize_entry("anonymous", &self.anonymous.unwrap_or(false))?;
+        }
+
+        map.end()
+    }
+}
+
+fn default_kind() -> String {
+    String::from("function")
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AbiDocument {
+    Items(Vec<AbiItem>),
+    Artifact { abi: Vec<AbiItem> },
+}
+
+pub fn parse_abi(json: &str) -> Result<Vec<AbiItem>, Error> {
+    let items = match serde_json::from_str::<AbiDocument>(json)? {
+        AbiDocument::Items(items) => items,
+        AbiDocument::Artifact { abi } => abi,
+    };
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_abi() {
+        let items = parse_abi(r#"[
+            {
+                "type": "function",
+                "name": "swap",
+                "inputs": [
+                    { "name": "path", "type": "address[]" },
+                    {
+                        "name": "orders",
+                        "type": "tuple[]",
+                        "components": [
+                            { "name": "maker", "type": "address" },
+                            { "name": "amounts", "type": "uint256[2]" }
+                        ]
+                    }
+                ],
+                "outputs": [],
+                "stateMutability": "nonpayable"
+            },
+            { "type": "event", "name": "Swap", "inputs": [], "anonymous": false }
+        ]"#).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "swap");
+        assert_eq!(items[0].inputs[0].canonical_type(), "address[]");
+        assert_eq!(items[0].inputs[1].canonical_type(), "(address,uint256[2])[]");
+        assert_eq!(items[1].kind, "event");
+    }
+
+    #[test]
+    fn test_parse_artifact() {
+        let items = parse_abi(r#"{ "contractName": "Token", "abi": [{ "name": "totalSupply", "outputs": [{ "type": "uint256" }] }] }"#).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].kind, "function");
+        assert_eq!(items[0].outputs[0].canonical_type(), "uint256");
+        assert_eq!(items[0].mutability(), StateMutability::NonPayable);
+    }
+
+    #[test]
+    fn test_legacy_mutability() {
+        let items = parse_abi(r#"[{ "name": "balanceOf", "constant": true }, { "name": "deposit", "payable": true }]"#).unwrap();
+        assert_eq!(items[0].mutability(), StateMutability::View);
+        assert_eq!(items[1].mutability(), StateMutability::Payable);
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let (params, codec) = parse_params(&["address indexed from", "(uint, bytes)[2] orders", "uint"], true).unwrap();
+        assert_eq!(codec.name(), "(address,(uint256,bytes)[2],uint256)");
+
+        assert_eq!(params[0].name, "from");
+        assert_eq!(params[0].kind, "address");
+        assert_eq!(params[0].indexed, Some(true));
+        assert_eq!(params[1].name, "orders");
+        assert_eq!(params[1].kind, "tuple[2]");
+        assert_eq!(params[1].canonical_type(), "(uint256,bytes)[2]");
+        assert_eq!(params[1].indexed, Some(false));
+        assert_eq!(params[2].name, "");
+        assert_eq!(params[2].kind, "uint256");
+
+        assert!(parse_params(&["address indexed from"], false).is_err());
+    }
+
+    #[test]
+    fn test_canonical_params() {
+        let items = parse_abi(r#"[{
+            "name": "fill",
+            "inputs": [
+                { "name": "amount", "type": "uint" },
+                {
+                    "name": "order",
+                    "type": "tuple[]",
+                    "components": [{ "name": "flag", "type": "byte" }, { "name": "delta", "type": "int" }]
+                }
+            ]
+        }]"#).unwrap();
+
+        let (params, codec) = canonical_params(&items[0].inputs).unwrap();
+        assert_eq!(codec.name(), "(uint256,(bytes1,int256)[])");
+        assert_eq!(params[0].name, "amount");
+        assert_eq!(params[0].kind, "uint256");
+        assert_eq!(params[1].kind, "tuple[]");
+        assert_eq!(params[1].components[0].name, "flag");
+        assert_eq!(params[1].components[0].kind, "bytes1");
+        assert_eq!(params[1].components[1].kind, "int256");
+    }
+
+    #[test]
+    fn test_serialize_abi_item() {
+        let mut item = AbiItem::new("event", "Transfer");
+        item.inputs.push(AbiParam::from_type("value", "uint256").unwrap());
+        assert_eq!(
+            serde_json::to_string(&item).unwrap(),
+            r#"{"type":"event","name":"Transfer","inputs":[{"name":"value","type":"uint256"}],"anonymous":false}"#,
+        );
+
+        let item = AbiItem::new("function", "totalSupply");
+        assert_eq!(
+            serde_json::to_string(&item).unwrap(),
+            r#"{"type":"function","name":"totalSupply","inputs":[],"outputs":[],"stateMutability":"nonpayable"}"#,
+        );
+
+        let item = AbiItem::new("receive", "");
+        assert_eq!(
+            serde_json::to_string(&item).unwrap(),
+            r#"{"type":"receive","stateMutability":"payable"}"#,
+        );
+    }
+}
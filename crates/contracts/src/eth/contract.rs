@@ -4,7 +4,7 @@ use rpc::network::EthereumNetwork;
 use rpc::channel::OneshotChannel;
 use rpc::jsonrpc;
 use crate::Error;
-use crate::eth::EthereumFunction;
+use crate::eth::{EthereumFunction, LenientDecoded};
 
 pub struct EthereumContract {
     network: Rc<EthereumNetwork>,
@@ -24,13 +24,22 @@ impl EthereumContract {
 
 impl EthereumContract {
     pub async fn invoke(&self, function: &EthereumFunction, args: Vec<Value>, tag: jsonrpc::Tag) -> Result<Option<Vec<Value>>, Error> {
+        let response = self.call(function, args, tag).await?;
+        response.map(|response| function.decode(response.as_slice())).transpose()
+    }
+
+    pub async fn invoke_lenient(&self, function: &EthereumFunction, args: Vec<Value>, tag: jsonrpc::Tag) -> Result<Option<LenientDecoded>, Error> {
+        let response = self.call(function, args, tag).await?;
+        response.map(|response| function.decode_lenient(response.as_slice())).transpose()
+    }
+
+    async fn call(&self, function: &EthereumFunction, args: Vec<Value>, tag: jsonrpc::Tag) -> Result<Option<Vec<u8>>, Error> {
         let hex_data = function.encode(args)?;
         let data = format!("0x{}", hex::encode(hex_data));
-        let response = match self.network.call(self.channel.as_ref(), self.address.as_str(), data.as_str(), tag).await {
-            Ok(response) => response,
-            Err(rpc_error) => Err(Error::RpcError(rpc_error))?,
-        };
-        response.map(|response| function.decode(response.as_slice())).transpose()
+        match self.network.call(self.channel.as_ref(), self.address.as_str(), data.as_str(), tag).await {
+            Ok(response) => Ok(response),
+            Err(rpc_error) => Err(Error::RpcError(rpc_error)),
+        }
     }
 }
 
@@ -50,7 +59,7 @@ mod test {
         };
         let network = Rc::new(EthereumNetwork::new(options));
 
-        const USDT: &'static str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+        const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
         let contract = EthereumContract::new(
             network,
             channel,
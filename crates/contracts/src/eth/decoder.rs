@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::path::Path;
+use ethabi::Value;
+use crate::Error;
+use crate::eth::{parse_abi, EthereumFunction};
+
+pub struct DecodedCall<'f> {
+    pub function: &'f EthereumFunction,
+    pub args: Vec<Value>,
+}
+
+#[derive(Default)]
+pub struct CalldataDecoder {
+    functions: HashMap<[u8; 4], Vec<EthereumFunction>>,
+}
+
+impl CalldataDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    pub fn register(&mut self, function: EthereumFunction) -> bool {
+        let candidates = self.functions.entry(function.selector()).or_default();
+        if candidates.iter().any(|candidate| candidate.signature() == function.signature()) {
+            return false;
+        }
+
+        candidates.push(function);
+        true
+    }
+
+    pub fn load_abi(&mut self, json: &str) -> Result<usize, Error> {
+        let mut registered = 0;
+        for item in parse_abi(json)?.iter().filter(|item| item.kind == "function") {
+            let function = EthereumFunction::from_abi(item)?;
+            registered += self.register(function) as usize;
+        }
+        Ok(registered)
+    }
+
+    pub fn load_abi_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, Error> {
+        let json = std::fs::read_to_string(path)?;
+        self.load_abi(&json)
+    }
+
+    // One signature per line, optionally prefixed by its selector: `0xa9059cbb transfer(address,uint256)`.
+    // Lines that don't parse, or whose selector doesn't match, are skipped and reported rather than
+    // failing the whole list, since dumps like 4byte's carry the odd malformed entry.
+    pub fn load_signatures(&mut self, text: &str) -> LoadedSignatures {
+        let mut loaded = LoadedSignatures::default();
+        for (index, line) in text.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match parse_signature_line(line) {
+                Ok(function) => loaded.registered += self.register(function) as usize,
+                Err(err) => loaded.skipped.push((index + 1, err)),
+            }
+        }
+        loaded
+    }
+
+    pub fn load_signatures_file<P: AsRef<Path>>(&mut self, path: P) -> Result<LoadedSignatures, Error> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(self.load_signatures(&text))
+    }
+
+    pub fn lookup(&self, selector: [u8; 4]) -> &[EthereumFunction] {
+        self.functions.get(&selector).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn decode(&self, calldata: &[u8]) -> Result<Vec<DecodedCall<'_>>, Error> {
+        let selector: [u8; 4] = calldata.get(..4)
+            .ok_or(Error::InvalidData)?
+            .try_into()
+            .expect("selector is 4 bytes");
+
+        let decoded = self.lookup(selector).iter()
+            .filter_map(|function| {
+                let args = function.decode_input(calldata).ok()?;
+                // Re-encoding must reproduce the input, otherwise the signature only decoded by accident.
+                let encoded = function.encode(args.clone()).ok()?;
+                (encoded == calldata).then_some(DecodedCall { function, args })
+            })
+            .collect();
+
+        Ok(decoded)
+    }
+}
+
+// The outcome of loading a signature list: how many new signatures were registered, and the
+// lines skipped, by line number, with the reason.
+#[derive(Debug, Default)]
+pub struct LoadedSignatures {
+    pub registered: usize,
+    pub skipped: Vec<(usize, Error)>,
+}
+
+fn parse_signature_line(line: &str) -> Result<EthereumFunction, Error> {
+    let (selector, signature) = match line.strip_prefix("0x") {
+        Some(rest) => match rest.split_once(|c: char| c.is_whitespace() || c == ',') {
+            Some((selector, signature)) => (Some(selector), signature.trim()),
+            None => Err(Error::InvalidSignature(line.to_string()))?,
+        },
+        None => (None, line),
+    };
+
+    let function = EthereumFunction::from_signature(signature, &[])?;
+    if let Some(selector) = selector {
+        if hex::decode(selector)? != function.selector() {
+            Err(Error::InvalidSignature(line.to_string()))?
+        }
+    }
+    Ok(function)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERC20: &str = r#"[
+        {
+            "type": "function",
+            "name": "transfer",
+            "inputs": [{ "name": "to", "type": "address" }, { "name": "value", "type": "uint256" }],
+            "outputs": [{ "name": "", "type": "bool" }],
+            "stateMutability": "nonpayable"
+        },
+        {
+            "type": "function",
+            "name": "balanceOf",
+            "inputs": [{ "name": "owner", "type": "address" }],
+            "outputs": [{ "name": "", "type": "uint256" }],
+            "stateMutability": "view"
+        },
+        {
+            "type": "event",
+            "name": "Transfer",
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint256", "indexed": false }
+            ],
+            "anonymous": false
+        }
+    ]"#;
+
+    #[test]
+    fn test_decode_from_abi() {
+        let mut decoder = CalldataDecoder::new();
+        assert_eq!(decoder.load_abi(ERC20).unwrap(), 2);
+        assert_eq!(decoder.load_signatures("0xa9059cbb transfer(address,uint256)").registered, 0);
+
+        let calldata = hex::decode(concat!(
+            "a9059cbb",
+            "000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface",
+            "00000000000000000000000000000000000000000000000000000000000003e8",
+        )).unwrap();
+
+        let decoded = decoder.decode(&calldata).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].function.signature(), "transfer(address,uint256)");
+        assert_eq!(decoded[0].args, vec![
+            Value::address("feedfacefeedfacefeedfacefeedfacefeedface").unwrap(),
+            Value::UInt(1000_u32.into()),
+        ]);
+
+        let returns = decoded[0].function.decode(&hex::decode(
+            "0000000000000000000000000000000000000000000000000000000000000001"
+        ).unwrap()).unwrap();
+        assert_eq!(returns, vec![Value::Boolean(true)]);
+    }
+
+    #[test]
+    fn test_decode_unknown_selector() {
+        let mut decoder = CalldataDecoder::new();
+        decoder.load_abi(ERC20).unwrap();
+
+        assert!(decoder.decode(&hex::decode("18160ddd").unwrap()).unwrap().is_empty());
+        assert!(matches!(decoder.decode(&[0xa9, 0x05]), Err(Error::InvalidData)));
+    }
+
+    #[test]
+    fn test_decode_selector_collision() {
+        let mut decoder = CalldataDecoder::new();
+        let registered = decoder.load_signatures(concat!(
+            "# burn(uint256) and collate_propagate_storage(bytes16) share 0x42966c68\n",
+            "burn(uint256)\n",
+            "0x42966c68,collate_propagate_storage(bytes16)\n",
+        )).registered;
+        assert_eq!(registered, 2);
+        assert_eq!(decoder.lookup([0x42, 0x96, 0x6c, 0x68]).len(), 2);
+
+        let calldata = hex::decode(concat!(
+            "42966c68",
+            "0000000000000000000000000000000000000000000000000de0b6b3a7640000",
+        )).unwrap();
+        let decoded = decoder.decode(&calldata).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].function.signature(), "burn(uint256)");
+
+        let calldata = hex::decode(concat!(
+            "42966c68",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+        let decoded = decoder.decode(&calldata).unwrap();
+        assert_eq!(decoded.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_mismatched_selector() {
+        let mut decoder = CalldataDecoder::new();
+        let loaded = decoder.load_signatures("0xdeadbeef transfer(address,uint256)");
+        assert!(matches!(loaded.skipped[..], [(1, Error::InvalidSignature(_))]));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_skips_bad_lines() {
+        let mut decoder = CalldataDecoder::new();
+        let loaded = decoder.load_signatures(concat!(
+            "transfer(address,uint256)\n",
+            "0xzz transfer(address,uint256)\n",
+            "balanceOf(address\n",
+            "approve(address,uint256)\n",
+        ));
+        assert_eq!(loaded.registered, 2);
+        assert_eq!(loaded.skipped.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(decoder.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_truncated_calldata() {
+        let mut decoder = CalldataDecoder::new();
+        decoder.load_signatures("transfer(address,uint256)");
+
+        let calldata = hex::decode(concat!(
+            "a9059cbb",
+            "000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface",
+        )).unwrap();
+        assert!(decoder.decode(&calldata).unwrap().is_empty());
+    }
+}
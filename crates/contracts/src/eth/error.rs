@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use ethabi::Value;
+use crate::Error;
+use crate::eth::{AbiItem, AbiParam};
+use crate::eth::abi::{canonical_params, canonical_types, parse_params};
+use crate::eth::signature::encode_4bytes;
+
+pub struct EthereumError {
+    pub name: String,
+    signature: String,
+    selector: [u8; 4],
+    inputs: Vec<AbiParam>,
+    codec: Arc<dyn ethabi::Codec>,
+}
+
+impl EthereumError {
+    pub fn new(name: &str, args: &[&str]) -> Result<Self, Error> {
+        let (inputs, codec) = parse_params(args, false)?;
+        Ok(Self::from_parts(name, inputs, codec))
+    }
+
+    pub fn from_abi(item: &AbiItem) -> Result<Self, Error> {
+        if item.kind != "error" {
+            return Err(Error::InvalidData);
+        }
+
+        let (inputs, codec) = canonical_params(&item.inputs)?;
+        Ok(Self::from_parts(&item.name, inputs, codec))
+    }
+
+    fn from_parts(name: &str, inputs: Vec<AbiParam>, codec: Arc<dyn ethabi::Codec>) -> Self {
+        let signature = format!("{}({})", name, canonical_types(&inputs).join(","));
+        let selector = encode_4bytes(&signature);
+
+        Self {
+            name: name.to_string(),
+            signature,
+            selector,
+            inputs,
+            codec,
+        }
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        self.selector
+    }
+
+    pub fn inputs(&self) -> &[AbiParam] {
+        &self.inputs
+    }
+
+    pub fn decode(&self, revert_data: &[u8]) -> Result<Vec<Value>, Error> {
+        match revert_data.split_at_checked(4) {
+            Some((selector, args)) if selector == self.selector => match self.codec.decode(args)? {
+                Value::Tuple(values) => Ok(values),
+                _ => panic!("Tuple decoder must return a tuple"),
+            },
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    pub fn abi(&self) -> AbiItem {
+        let mut item = AbiItem::new("error", &self.name);
+        item.inputs = self.inputs.clone();
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_decode() {
+        let error = EthereumError::new("InsufficientBalance", &["uint256 available", "uint256 required"]).unwrap();
+        assert_eq!(error.signature(), "InsufficientBalance(uint256,uint256)");
+
+        let mut revert_data = error.selector().to_vec();
+        revert_data.extend(hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+        )).unwrap());
+
+        assert_eq!(
+            error.decode(&revert_data).unwrap(),
+            vec![Value::UInt(1_u8.into()), Value::UInt(2_u8.into())],
+        );
+        assert!(matches!(error.decode(&revert_data[4..]), Err(Error::InvalidData)));
+    }
+}
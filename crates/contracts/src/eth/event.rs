@@ -0,0 +1,99 @@
+use crate::Error;
+use crate::eth::{AbiItem, AbiParam};
+use crate::eth::abi::{canonical_params, canonical_types, parse_params};
+use crate::eth::signature::encode_topic;
+
+pub struct EthereumEvent {
+    pub name: String,
+    pub anonymous: bool,
+    signature: String,
+    topic: [u8; 32],
+    inputs: Vec<AbiParam>,
+}
+
+impl EthereumEvent {
+    pub fn new(name: &str, params: &[&str]) -> Result<Self, Error> {
+        let (inputs, _) = parse_params(params, true)?;
+        Ok(Self::from_parts(name, inputs, false))
+    }
+
+    pub fn from_abi(item: &AbiItem) -> Result<Self, Error> {
+        if item.kind != "event" {
+            return Err(Error::InvalidData);
+        }
+
+        let (inputs, _) = canonical_params(&item.inputs)?;
+        Ok(Self::from_parts(&item.name, inputs, item.anonymous.unwrap_or(false)))
+    }
+
+    fn from_parts(name: &str, inputs: Vec<AbiParam>, anonymous: bool) -> Self {
+        let signature = format!("{}({})", name, canonical_types(&inputs).join(","));
+        let topic = encode_topic(&signature);
+
+        Self {
+            name: name.to_string(),
+            anonymous,
+            signature,
+            topic,
+            inputs,
+        }
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn topic(&self) -> [u8; 32] {
+        self.topic
+    }
+
+    pub fn inputs(&self) -> &[AbiParam] {
+        &self.inputs
+    }
+
+    pub fn abi(&self) -> AbiItem {
+        let mut item = AbiItem::new("event", &self.name);
+        item.inputs = self.inputs.clone();
+        item.anonymous = Some(self.anonymous);
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_topic() {
+        let event = EthereumEvent::new("Transfer", &["address indexed from", "address indexed to", "uint256 value"]).unwrap();
+        assert_eq!(event.signature(), "Transfer(address,address,uint256)");
+        assert_eq!(
+            hex::encode(event.topic()),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+        );
+        assert_eq!(event.inputs()[0].indexed, Some(true));
+        assert_eq!(event.inputs()[2].indexed, Some(false));
+    }
+
+    #[test]
+    fn test_from_abi_aliases() {
+        let items = crate::eth::parse_abi(r#"[{
+            "type": "event",
+            "name": "Transfer",
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint", "indexed": false }
+            ],
+            "anonymous": false
+        }]"#).unwrap();
+
+        let event = EthereumEvent::from_abi(&items[0]).unwrap();
+        assert_eq!(event.signature(), "Transfer(address,address,uint256)");
+        assert_eq!(
+            hex::encode(event.topic()),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+        );
+        assert_eq!(event.inputs()[0].indexed, Some(true));
+    }
+}
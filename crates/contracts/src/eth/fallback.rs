@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use ethabi::{CodecCache, Value};
+use crate::Error;
+
+pub enum Fallback {
+    // Decode with a different set of return types.
+    Returns(Arc<dyn ethabi::Codec>),
+    // A single `bytes32` holding text, trailing NULs removed, yielding a `Value::String`.
+    TrimmedBytes32,
+    // Empty return data, yielding the given values.
+    Empty(Vec<Value>),
+}
+
+impl Fallback {
+    pub fn returns(types: &[&str]) -> Result<Self, Error> {
+        Ok(Fallback::Returns(CodecCache::global().parse(types)?))
+    }
+
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Result<Vec<Value>, Error> {
+        match self {
+            Fallback::Returns(codec) => match codec.decode(bytes)? {
+                Value::Tuple(values) => Ok(values),
+                _ => panic!("Tuple decoder must return a tuple"),
+            },
+            Fallback::TrimmedBytes32 => {
+                let word = bytes.get(..32).ok_or(Error::InvalidData)?;
+                let end = word.iter().rposition(|&byte| byte != 0).map_or(0, |index| index + 1);
+                let text = std::str::from_utf8(&word[..end]).map_err(|_| Error::InvalidData)?;
+                Ok(vec![Value::String(text.to_string())])
+            }
+            Fallback::Empty(values) if bytes.is_empty() => Ok(values.clone()),
+            Fallback::Empty(_) => Err(Error::InvalidData),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Matched {
+    Declared,
+    // Index into the function's fallbacks.
+    Fallback(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LenientDecoded {
+    pub values: Vec<Value>,
+    pub matched: Matched,
+}
@@ -1,30 +1,101 @@
+use std::sync::Arc;
 use ethabi::Value;
 use crate::Error;
-use crate::eth::signature::encode_4bytes;
+use crate::eth::{AbiItem, AbiParam, Fallback, LenientDecoded, Matched, StateMutability};
+use crate::eth::abi::{canonical_params, canonical_types, parse_params};
+use crate::eth::signature::{encode_4bytes, split_signature};
 
 pub struct EthereumFunction {
     pub name: String,
+    pub state_mutability: StateMutability,
+    signature: String,
     selector: [u8; 4],
-    arg_codec: Box<dyn ethabi::Codec>,
-    ret_codec: Box<dyn ethabi::Codec>,
+    inputs: Vec<AbiParam>,
+    outputs: Vec<AbiParam>,
+    arg_codec: Arc<dyn ethabi::Codec>,
+    ret_codec: Arc<dyn ethabi::Codec>,
+    fallbacks: Vec<Fallback>,
 }
 
 impl EthereumFunction {
     pub fn new(name: &str, args: &[&str], returns: &[&str]) -> Result<Self, Error> {
-        let signature = format!("{}({})", name, args.join(","));
+        let (inputs, arg_codec) = parse_params(args, false)?;
+        let (outputs, ret_codec) = parse_params(returns, false)?;
+        Ok(Self::from_parts(name, inputs, outputs, arg_codec, ret_codec))
+    }
+
+    fn from_parts(
+        name: &str,
+        inputs: Vec<AbiParam>,
+        outputs: Vec<AbiParam>,
+        arg_codec: Arc<dyn ethabi::Codec>,
+        ret_codec: Arc<dyn ethabi::Codec>,
+    ) -> Self {
+        let signature = format!("{}({})", name, canonical_types(&inputs).join(","));
         let selector = encode_4bytes(&signature);
-        let arg_codec = ethabi::parse(args)?;
-        let ret_codec = ethabi::parse(returns)?;
-
-        let function = Self {
-             name: name.to_string(),
-             selector,
-             arg_codec,
-             ret_codec,
-            };
+
+        Self {
+            name: name.to_string(),
+            state_mutability: StateMutability::default(),
+            signature,
+            selector,
+            inputs,
+            outputs,
+            arg_codec,
+            ret_codec,
+            fallbacks: Vec::new(),
+        }
+    }
+
+    pub fn from_signature(signature: &str, returns: &[&str]) -> Result<Self, Error> {
+        let (name, args) = split_signature(signature)?;
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        Self::new(&name, &args, returns)
+    }
+
+    pub fn from_abi(item: &AbiItem) -> Result<Self, Error> {
+        if item.kind != "function" {
+            return Err(Error::InvalidData);
+        }
+
+        let (inputs, arg_codec) = canonical_params(&item.inputs)?;
+        let (outputs, ret_codec) = canonical_params(&item.outputs)?;
+
+        let mut function = Self::from_parts(&item.name, inputs, outputs, arg_codec, ret_codec);
+        function.state_mutability = item.mutability();
         Ok(function)
     }
 
+    // Alternatives tried in order by `decode_lenient` when the declared return types do not decode.
+    pub fn with_fallbacks(mut self, fallbacks: Vec<Fallback>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        self.selector
+    }
+
+    pub fn inputs(&self) -> &[AbiParam] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[AbiParam] {
+        &self.outputs
+    }
+
+    pub fn abi(&self) -> AbiItem {
+        let mut item = AbiItem::new("function", &self.name);
+        item.inputs = self.inputs.clone();
+        item.outputs = self.outputs.clone();
+        item.state_mutability = Some(self.state_mutability);
+        item
+    }
+
     pub fn encode(&self, value: Vec<Value>) -> Result<Vec<u8>, Error> {
         let tuple = Value::Tuple(value);
         let encoded = match self.arg_codec.encode(&tuple) {
@@ -38,20 +109,45 @@ impl EthereumFunction {
     }
 
     pub fn decode(&self, bytes: &[u8]) -> Result<Vec<Value>, Error> {
-        let decoded = match self.ret_codec.decode(bytes) {
-            Ok(decoded) => decoded,
-            Err(ethabi::Error::InvalidData) => Err(Error::InvalidData)?,
-            Err(ethabi::Error::Hex(hex_error)) => Err(hex_error)?,
-            Err(uncaught_error) => panic!("uncaught error: {:?}", uncaught_error),
+        decode_tuple(self.ret_codec.as_ref(), bytes)
+    }
+
+    pub fn decode_lenient(&self, bytes: &[u8]) -> Result<LenientDecoded, Error> {
+        let error = match self.decode(bytes) {
+            Ok(values) => return Ok(LenientDecoded { values, matched: Matched::Declared }),
+            Err(error) => error,
         };
 
-        match decoded {
-            Value::Tuple(values) => Ok(values),
-            _ => panic!("Tuple decoder must return a tuple"),
+        self.fallbacks.iter().enumerate()
+            .find_map(|(index, fallback)| {
+                let values = fallback.decode(bytes).ok()?;
+                Some(LenientDecoded { values, matched: Matched::Fallback(index) })
+            })
+            .ok_or(error)
+    }
+
+    pub fn decode_input(&self, calldata: &[u8]) -> Result<Vec<Value>, Error> {
+        match calldata.split_at_checked(4) {
+            Some((selector, args)) if selector == self.selector => decode_tuple(self.arg_codec.as_ref(), args),
+            _ => Err(Error::InvalidData),
         }
     }
 }
 
+fn decode_tuple(codec: &dyn ethabi::Codec, bytes: &[u8]) -> Result<Vec<Value>, Error> {
+    let decoded = match codec.decode(bytes) {
+        Ok(decoded) => decoded,
+        Err(ethabi::Error::InvalidData) => Err(Error::InvalidData)?,
+        Err(ethabi::Error::Hex(hex_error)) => Err(hex_error)?,
+        Err(uncaught_error) => panic!("uncaught error: {:?}", uncaught_error),
+    };
+
+    match decoded {
+        Value::Tuple(values) => Ok(values),
+        _ => panic!("Tuple decoder must return a tuple"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,6 +175,94 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_signature() {
+        let function = EthereumFunction::from_signature("transfer(address, uint256)", &["bool"]).unwrap();
+        assert_eq!(function.name, "transfer");
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+    }
+
+    #[test]
+    fn test_from_abi_aliases() {
+        let items = crate::eth::parse_abi(r#"[{
+            "name": "transfer",
+            "inputs": [{ "name": "to", "type": "address" }, { "name": "amount", "type": "uint" }],
+            "outputs": [{ "type": "bool" }]
+        }]"#).unwrap();
+
+        let function = EthereumFunction::from_abi(&items[0]).unwrap();
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(function.inputs()[1].kind, "uint256");
+    }
+
+    #[test]
+    fn test_named_params() {
+        let function = EthereumFunction::new("transfer", &["address to", "uint amount"], &["bool success"]).unwrap();
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(function.inputs()[1].name, "amount");
+        assert_eq!(function.inputs()[1].kind, "uint256");
+        assert_eq!(function.outputs()[0].name, "success");
+    }
+
+    #[test]
+    fn test_decode_input() {
+        let function = EthereumFunction::new("balanceOf", &["address"], &["uint256"]).unwrap();
+
+        let calldata = hex::decode("70a08231000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface").unwrap();
+        let decoded = function.decode_input(&calldata).unwrap();
+        assert_eq!(
+            decoded,
+            vec![Value::address("feedfacefeedfacefeedfacefeedfacefeedface").unwrap()],
+        );
+
+        let calldata = hex::decode("18160ddd").unwrap();
+        assert!(matches!(function.decode_input(&calldata), Err(Error::InvalidData)));
+    }
+
+    #[test]
+    fn test_shared_codecs() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let first = EthereumFunction::new("transfer", &["address to", "uint amount"], &["bool"]).unwrap();
+        let second = EthereumFunction::from_signature("transfer(address,uint256)", &["bool"]).unwrap();
+        assert!(Arc::ptr_eq(&first.arg_codec, &second.arg_codec));
+        assert!(Arc::ptr_eq(&first.ret_codec, &second.ret_codec));
+        assert_send_sync(&first);
+    }
+
+    #[test]
+    fn test_decode_lenient() {
+        let function = EthereumFunction::new("symbol", &[], &["string"]).unwrap()
+            .with_fallbacks(vec![
+                Fallback::TrimmedBytes32,
+                Fallback::Empty(vec![Value::String(String::new())]),
+            ]);
+
+        let string = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "5553445400000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+        let decoded = function.decode_lenient(&string).unwrap();
+        assert_eq!(decoded.values, vec![Value::String("USDT".to_string())]);
+        assert_eq!(decoded.matched, Matched::Declared);
+
+        let bytes32 = hex::decode("4d4b520000000000000000000000000000000000000000000000000000000000").unwrap();
+        assert!(function.decode(&bytes32).is_err());
+        let decoded = function.decode_lenient(&bytes32).unwrap();
+        assert_eq!(decoded.values, vec![Value::String("MKR".to_string())]);
+        assert_eq!(decoded.matched, Matched::Fallback(0));
+
+        let decoded = function.decode_lenient(&[]).unwrap();
+        assert_eq!(decoded.values, vec![Value::String(String::new())]);
+        assert_eq!(decoded.matched, Matched::Fallback(1));
+
+        assert!(matches!(function.decode_lenient(&[0xff; 16]), Err(Error::InvalidData)));
+    }
+
     #[test]
     fn test_decode() {
         let args = &["address"];
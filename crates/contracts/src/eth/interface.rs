@@ -0,0 +1,259 @@
+use std::fmt::Write;
+use crate::Error;
+use crate::eth::{AbiItem, AbiParam, EthereumError, EthereumEvent, EthereumFunction, StateMutability};
+
+pub struct EthereumInterface {
+    pub name: String,
+    pub functions: Vec<EthereumFunction>,
+    pub events: Vec<EthereumEvent>,
+    pub errors: Vec<EthereumError>,
+}
+
+impl EthereumInterface {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            functions: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    // Items that cannot be declared in an interface (constructor, fallback, receive) are skipped.
+    pub fn from_abi(name: &str, items: &[AbiItem]) -> Result<Self, Error> {
+        let mut interface = Self::new(name);
+        for item in items {
+            match item.kind.as_str() {
+                "function" => interface.functions.push(EthereumFunction::from_abi(item)?),
+                "event" => interface.events.push(EthereumEvent::from_abi(item)?),
+                "error" => interface.errors.push(EthereumError::from_abi(item)?),
+                _ => {}
+            }
+        }
+        Ok(interface)
+    }
+
+    pub fn abi(&self) -> Vec<AbiItem> {
+        let functions = self.functions.iter().map(EthereumFunction::abi);
+        let events = self.events.iter().map(EthereumEvent::abi);
+        let errors = self.errors.iter().map(EthereumError::abi);
+        functions.chain(events).chain(errors).collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self.abi())?)
+    }
+
+    pub fn to_solidity(&self) -> String {
+        let mut structs = Structs::default();
+        let mut body = Vec::new();
+
+        for error in &self.errors {
+            let params = error.inputs().iter().enumerate()
+                .map(|(i, param)| declare(&mut structs, param, None, i, false))
+                .collect::<Vec<_>>();
+            body.push(format!("error {}({});", error.name, params.join(", ")));
+        }
+
+        for event in &self.events {
+            let params = event.inputs().iter()
+                .map(|param| {
+                    let indexed = if param.indexed == Some(true) { " indexed" } else { "" };
+                    let ty = structs.solidity_type(param);
+                    match param.name.as_str() {
+                        "" => format!("{}{}", ty, indexed),
+                        name => format!("{}{} {}", ty, indexed, name),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let anonymous = if event.anonymous { " anonymous" } else { "" };
+            body.push(format!("event {}({}){};", event.name, params.join(", "), anonymous));
+        }
+
+        for function in &self.functions {
+            let inputs = function.inputs().iter().enumerate()
+                .map(|(i, param)| declare(&mut structs, param, Some("calldata"), i, false))
+                .collect::<Vec<_>>();
+            let outputs = function.outputs().iter().enumerate()
+                .map(|(i, param)| declare(&mut structs, param, Some("memory"), i, false))
+                .collect::<Vec<_>>();
+
+            let mut line = format!("function {}({}) external", function.name, inputs.join(", "));
+            match function.state_mutability {
+                StateMutability::Pure => line.push_str(" pure"),
+                StateMutability::View => line.push_str(" view"),
+                StateMutability::Payable => line.push_str(" payable"),
+                StateMutability::NonPayable => {}
+            }
+            if !outputs.is_empty() {
+                write!(line, " returns ({})", outputs.join(", ")).unwrap();
+            }
+            line.push(';');
+            body.push(line);
+        }
+
+        let mut source = String::new();
+        writeln!(source, "// SPDX-License-Identifier: UNLICENSED").unwrap();
+        writeln!(source, "pragma solidity ^0.8.4;").unwrap();
+        writeln!(source).unwrap();
+        writeln!(source, "interface {} {{", self.name).unwrap();
+        for (name, members) in &structs.definitions {
+            writeln!(source, "    struct {} {{", name).unwrap();
+            for member in members {
+                writeln!(source, "        {};", member).unwrap();
+            }
+            writeln!(source, "    }}").unwrap();
+            writeln!(source).unwrap();
+        }
+        for line in body {
+            writeln!(source, "    {}", line).unwrap();
+        }
+        writeln!(source, "}}").unwrap();
+        source
+    }
+}
+
+// Struct definitions collected while rendering, keyed by their canonical tuple type so that
+// identical tuples share one struct.
+#[derive(Default)]
+struct Structs {
+    canonical: Vec<String>,
+    definitions: Vec<(String, Vec<String>)>,
+}
+
+impl Structs {
+    fn solidity_type(&mut self, param: &AbiParam) -> String {
+        match param.kind.strip_prefix("tuple") {
+            Some(array) => format!("{}{}", self.register(param), array),
+            None => param.kind.clone(),
+        }
+    }
+
+    fn register(&mut self, param: &AbiParam) -> String {
+        let canonical = param.canonical_type();
+        let canonical = canonical[..canonical.rfind(')').unwrap() + 1].to_string();
+        if let Some(index) = self.canonical.iter().position(|known| *known == canonical) {
+            return self.definitions[index].0.clone();
+        }
+
+        let members = param.components.iter().enumerate()
+            .map(|(i, component)| declare(self, component, None, i, true))
+            .collect::<Vec<_>>();
+
+        let base = struct_name(param).unwrap_or_else(|| format!("Struct{}", self.definitions.len()));
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while self.definitions.iter().any(|(known, _)| *known == name) {
+            name = format!("{}{}", base, suffix);
+            suffix += 1;
+        }
+
+        self.canonical.push(canonical);
+        self.definitions.push((name.clone(), members));
+        name
+    }
+}
+
+fn declare(structs: &mut Structs, param: &AbiParam, location: Option<&str>, index: usize, named: bool) -> String {
+    let mut declaration = structs.solidity_type(param);
+    if let Some(location) = location {
+        if is_reference(param) {
+            write!(declaration, " {}", location).unwrap();
+        }
+    }
+    match param.name.as_str() {
+        "" if named => write!(declaration, " field{}", index).unwrap(),
+        "" => {}
+        name => write!(declaration, " {}", name).unwrap(),
+    }
+    declaration
+}
+
+fn is_reference(param: &AbiParam) -> bool {
+    param.is_tuple() || param.kind.ends_with(']') || param.kind == "bytes" || param.kind == "string"
+}
+
+fn struct_name(param: &AbiParam) -> Option<String> {
+    if let Some(internal_type) = param.internal_type.as_deref().and_then(|ty| ty.strip_prefix("struct ")) {
+        let name = internal_type.split('[').next().unwrap_or_default();
+        let name = name.rsplit('.').next().unwrap_or_default();
+        if !name.is_empty() {
+            return Some(name.to_string());
+        }
+    }
+
+    let mut chars = param.name.trim_start_matches('_').chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth::parse_abi;
+
+    #[test]
+    fn test_to_solidity() {
+        let mut interface = EthereumInterface::new("IToken");
+
+        let mut balance_of = EthereumFunction::new("balanceOf", &["address owner"], &["uint256"]).unwrap();
+        balance_of.state_mutability = StateMutability::View;
+        interface.functions.push(balance_of);
+        interface.functions.push(EthereumFunction::new("fill", &["(address, uint256[])[] orders", "bytes"], &["bool"]).unwrap());
+        interface.events.push(EthereumEvent::new("Transfer", &["address indexed from", "address indexed to", "uint256 value"]).unwrap());
+        interface.errors.push(EthereumError::new("Unauthorized", &["address"]).unwrap());
+
+        assert_eq!(interface.to_solidity(), concat!(
+            "// SPDX-License-Identifier: UNLICENSED\n",
+            "pragma solidity ^0.8.4;\n",
+            "\n",
+            "interface IToken {\n",
+            "    struct Orders {\n",
+            "        address field0;\n",
+            "        uint256[] field1;\n",
+            "    }\n",
+            "\n",
+            "    error Unauthorized(address);\n",
+            "    event Transfer(address indexed from, address indexed to, uint256 value);\n",
+            "    function balanceOf(address owner) external view returns (uint256);\n",
+            "    function fill(Orders[] calldata orders, bytes calldata) external returns (bool);\n",
+            "}\n",
+        ));
+    }
+
+    #[test]
+    fn test_abi_roundtrip() {
+        let json = r#"[
+            {
+                "type": "function",
+                "name": "swap",
+                "inputs": [
+                    {
+                        "name": "order",
+                        "type": "tuple",
+                        "internalType": "struct Router.Order",
+                        "components": [
+                            { "name": "maker", "type": "address" },
+                            { "name": "amount", "type": "uint256" }
+                        ]
+                    }
+                ],
+                "outputs": [],
+                "stateMutability": "payable"
+            },
+            { "type": "constructor", "inputs": [] },
+            { "type": "event", "name": "Swapped", "inputs": [{ "name": "maker", "type": "address", "indexed": true }], "anonymous": true }
+        ]"#;
+
+        let interface = EthereumInterface::from_abi("IRouter", &parse_abi(json).unwrap()).unwrap();
+        assert_eq!(interface.functions.len(), 1);
+        assert_eq!(interface.functions[0].signature(), "swap((address,uint256))");
+
+        let items = parse_abi(&interface.to_json().unwrap()).unwrap();
+        assert_eq!(items, interface.abi());
+
+        let source = interface.to_solidity();
+        assert!(source.contains("    struct Order {\n        address maker;\n        uint256 amount;\n    }\n"));
+        assert!(source.contains("function swap(Order calldata order) external payable;"));
+        assert!(source.contains("event Swapped(address indexed maker) anonymous;"));
+    }
+}
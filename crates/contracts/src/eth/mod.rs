@@ -1,6 +1,18 @@
+pub use abi::{parse_abi, AbiItem, AbiParam, StateMutability};
 pub use contract::EthereumContract;
+pub use decoder::{CalldataDecoder, DecodedCall, LoadedSignatures};
+pub use error::EthereumError;
+pub use event::EthereumEvent;
+pub use fallback::{Fallback, LenientDecoded, Matched};
 pub use function::EthereumFunction;
+pub use interface::EthereumInterface;
 
+mod abi;
 mod contract;
+mod decoder;
+mod error;
+mod event;
+mod fallback;
 mod function;
+mod interface;
 mod signature;
@@ -1,4 +1,16 @@
 use tiny_keccak::{Hasher, Keccak};
+use crate::Error;
+
+pub fn encode_topic(signature: &str) -> [u8; 32] {
+    let mut output = [0; 32];
+    {
+        let mut hasher = Keccak::v256();
+        hasher.update(signature.as_bytes());
+        hasher.finalize(&mut output);
+    }
+
+    output
+}
 
 pub fn encode_4bytes(signature: &str) -> [u8; 4] {
     let mut output = [0; 4];
@@ -11,6 +23,57 @@ pub fn encode_4bytes(signature: &str) -> [u8; 4] {
     output
 }
 
+pub fn split_signature(signature: &str) -> Result<(String, Vec<String>), Error> {
+    let invalid = || Error::InvalidSignature(signature.to_string());
+
+    let compact = signature.split_whitespace().collect::<String>();
+    let (name, rest) = compact.split_once('(').ok_or_else(invalid)?;
+    let args = rest.strip_suffix(')').ok_or_else(invalid)?;
+
+    if !is_identifier(name) {
+        return Err(invalid());
+    }
+
+    let types = split_types(args).ok_or_else(invalid)?;
+    Ok((name.to_string(), types.into_iter().map(String::from).collect()))
+}
+
+pub fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+// Splits a comma separated type list at the top level, leaving tuple components intact.
+pub fn split_types(types: &str) -> Option<Vec<&str>> {
+    let mut split = Vec::new();
+    let mut depth = 0_usize;
+    let mut begin = 0;
+    for (index, c) in types.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                split.push(types[begin..index].trim());
+                begin = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if depth != 0 {
+        return None;
+    }
+    if !types.trim().is_empty() {
+        split.push(types[begin..].trim());
+    }
+    if split.iter().any(|t| t.is_empty()) {
+        return None;
+    }
+
+    Some(split)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -22,4 +85,32 @@ mod tests {
             [0x70, 0xa0, 0x82, 0x31],
         );
     }
+
+    #[test]
+    fn test_encode_topic() {
+        assert_eq!(
+            hex::encode(encode_topic("Transfer(address,address,uint256)")),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+        );
+    }
+
+    #[test]
+    fn test_split_signature() {
+        assert_eq!(
+            split_signature("totalSupply()").unwrap(),
+            ("totalSupply".to_string(), vec![]),
+        );
+        assert_eq!(
+            split_signature("swap(address[], (address,uint256[2])[],bytes)").unwrap(),
+            ("swap".to_string(), vec![
+                "address[]".to_string(),
+                "(address,uint256[2])[]".to_string(),
+                "bytes".to_string(),
+            ]),
+        );
+
+        for signature in ["transfer", "transfer(address", "(address)", "f(address,)", "f((uint256)", "1f()"] {
+            assert!(split_signature(signature).is_err(), "{}", signature);
+        }
+    }
 }
@@ -1,6 +1,8 @@
 extern crate ethabi;
 extern crate hex;
 extern crate rpc;
+extern crate serde;
+extern crate serde_json;
 extern crate tiny_keccak;
 #[macro_use]
 extern crate thiserror;
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use crate::{parse, Codec, Error};
+
+// Parsed codecs keyed by their canonical signature, e.g. `(address,uint256)`. Different spellings
+// of the same types (`uint` and `uint256`) resolve to one shared codec.
+#[derive(Default)]
+pub struct CodecCache {
+    inner: RwLock<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    codecs: HashMap<String, Arc<dyn Codec>>,
+    aliases: HashMap<String, String>,
+}
+
+impl CodecCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn global() -> &'static CodecCache {
+        static GLOBAL: OnceLock<CodecCache> = OnceLock::new();
+        GLOBAL.get_or_init(CodecCache::new)
+    }
+
+    pub fn parse(&self, types: &[&str]) -> Result<Arc<dyn Codec>, Error> {
+        let alias = types.join(",");
+        {
+            let entries = self.inner.read().unwrap();
+            if let Some(codec) = entries.aliases.get(&alias).and_then(|canonical| entries.codecs.get(canonical)) {
+                return Ok(codec.clone());
+            }
+        }
+
+        let codec: Arc<dyn Codec> = Arc::from(parse(types)?);
+        let canonical = codec.name().to_string();
+
+        let mut entries = self.inner.write().unwrap();
+        let codec = entries.codecs.entry(canonical.clone()).or_insert(codec).clone();
+        entries.aliases.insert(alias, canonical);
+        Ok(codec)
+    }
+
+    pub fn get(&self, canonical: &str) -> Option<Arc<dyn Codec>> {
+        self.inner.read().unwrap().codecs.get(canonical).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().codecs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.inner.write().unwrap();
+        entries.codecs.clear();
+        entries.aliases.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_codecs() {
+        let cache = CodecCache::new();
+        let first = cache.parse(&["address", "uint"]).unwrap();
+        let second = cache.parse(&["address", "uint256"]).unwrap();
+        let third = cache.parse(&["address", "uint"]).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first, &third));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("(address,uint256)").is_some());
+
+        assert!(cache.parse(&["uint7"]).is_err());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_parse() {
+        let cache = Arc::new(CodecCache::new());
+        let handles = (0..8).map(|_| {
+            let cache = cache.clone();
+            std::thread::spawn(move || cache.parse(&["bytes32", "string[]"]).unwrap())
+        }).collect::<Vec<_>>();
+
+        let codecs = handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>();
+        assert!(codecs.iter().all(|codec| Arc::ptr_eq(codec, &codecs[0])));
+    }
+}
@@ -1,5 +1,7 @@
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{Layout, Role, Section};
 
 pub struct AddressCodec;
 
@@ -10,13 +12,18 @@ impl sealed::AbiType for AddressCodec {
 
 impl sealed::Decoder for AddressCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
-        if frame.len() >= 20 {
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
+        if frame.len() >= 32 {
             Ok(Value::Address(frame[12..32].to_vec()))
         } else {
             Err(Error::InvalidData)
         }
+    }
 
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        layout.check(position, 32)?;
+        layout.push(position, section, Role::Value, path, sealed::AbiType::name(self), 12);
+        Ok(())
     }
 }
 
@@ -50,6 +57,7 @@ fn encode_address(address: &str) -> Result<Vec<u8>, Error> {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_concat)]
 mod tests {
     use super::*;
     use crate::codec::{Encoder, Decoder};
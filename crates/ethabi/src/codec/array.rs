@@ -1,8 +1,10 @@
 use num_traits::ToPrimitive;
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::codec::{Codec, Encoder};
 use crate::codec::UIntCodec;
 use crate::{Value, Error};
+use crate::layout::{annotate_element, Layout, Role, Section};
 
 pub struct FixedArrayCodec {
     name: String,
@@ -42,23 +44,31 @@ impl sealed::Encoder for FixedArrayCodec {
 
 impl sealed::Decoder for FixedArrayCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
-        let mut values = Vec::with_capacity(self.size);
+        let mut values = Vec::with_capacity(self.size.min(frame.len() / 32));
         for index in 0..self.size {
             let value = if self.codec.is_dynamic() {
                 let head = UIntCodec::new(256).decode_frame(frame, 32 * index)?;
                 let head = head.as_uint()?;
-                let frame_base = head.to_usize().unwrap();
+                let frame_base = head.to_usize().ok_or(Error::InvalidData)?;
                 self.codec.decode_frame(frame, frame_base)?
             } else {
-                self.codec.decode_frame(&frame, 32 * index)?
+                self.codec.decode_frame(frame, 32 * index)?
             };
             values.push(value);
         }
 
         Ok(Value::Array(values))
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        for index in 0..self.size {
+            let path = format!("{}[{}]", path, index);
+            annotate_element(layout, self.codec.as_ref(), position, index, &path, section)?;
+        }
+        Ok(())
+    }
 }
 
 
@@ -97,7 +107,7 @@ impl sealed::Encoder for DynamicArrayCodec {
             }
         } else{
             let head_size = 32 * (values.len());
-            let mut tail_offset = std::iter::once(0).chain(tail_chunks.iter().scan(0, |offset, chunk| {
+            let mut tail_offset = core::iter::once(0).chain(tail_chunks.iter().scan(0, |offset, chunk| {
                 *offset += chunk.len();
                 Some(*offset)
             }));
@@ -120,19 +130,23 @@ impl sealed::Encoder for DynamicArrayCodec {
 
 impl sealed::Decoder for DynamicArrayCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
         let head = UIntCodec::new(256).decode_frame(frame, 0)?;
         let head = head.as_uint()?;
-        let length = head.to_usize().unwrap();
+        let length = head.to_usize().ok_or(Error::InvalidData)?;
 
         let frame = &frame[32..];
+        if length > frame.len() / 32 {
+            return Err(Error::InvalidData)
+        }
+
         let mut values = Vec::with_capacity(length);
 
         for index in 0..length {
             let value = if self.codec.is_dynamic() {
                 let head = UIntCodec::new(256).decode_frame(frame, 32 * index)?;
                 let head = head.as_uint()?;
-                let frame_base = head.to_usize().unwrap();
+                let frame_base = head.to_usize().ok_or(Error::InvalidData)?;
                 self.codec.decode_frame(frame, frame_base)?
             } else {
                 self.codec.decode_frame(frame, 32 * index)?
@@ -141,6 +155,20 @@ impl sealed::Decoder for DynamicArrayCodec {
         }
         Ok(Value::Array(values))
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        let length = layout.read_usize(position)?;
+        if length > layout.bytes().len().saturating_sub(position + 32) / 32 {
+            return Err(Error::InvalidData)
+        }
+        layout.push(position, section, Role::Length(length), path, sealed::AbiType::name(self), 0);
+
+        for index in 0..length {
+            let path = format!("{}[{}]", path, index);
+            annotate_element(layout, self.codec.as_ref(), position + 32, index, &path, section)?;
+        }
+        Ok(())
+    }
 }
 
 
@@ -173,6 +201,17 @@ mod tests {
             codec.decode(&bytes).unwrap());
     }
 
+    #[test]
+    fn test_oversized_dynamic_array_decoder() {
+        let bytes = hex::decode(concat!(
+            "00000000000000000000000000000000000000000000000000ffffffffffffff",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        )).unwrap();
+
+        let codec = DynamicArrayCodec::new(Box::new(UIntCodec::new(256)));
+        assert_eq!(codec.decode(&bytes), Err(crate::Error::InvalidData));
+    }
+
     #[test]
     fn test_fixed_array_encoder() {
         let codec = FixedArrayCodec::new(
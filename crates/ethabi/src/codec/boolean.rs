@@ -1,5 +1,7 @@
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{Layout, Role, Section};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct BooleanCodec;
@@ -22,15 +24,21 @@ impl sealed::Encoder for BooleanCodec {
 
 impl sealed::Decoder for BooleanCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
         if frame.len() < 32 {
-            return Err(Error::InvalidData)
+            Err(Error::InvalidData)
         } else {
             let bytes = &frame[..32];
             let value = bytes.iter().rev().any(|&x| x != 0);
             Ok(Value::Boolean(value))
         }
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        layout.check(position, 32)?;
+        layout.push(position, section, Role::Value, path, sealed::AbiType::name(self), 31);
+        Ok(())
+    }
 }
 
 
@@ -1,6 +1,8 @@
 use num_traits::ToPrimitive;
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{Layout, Role, Section};
 use crate::codec::Encoder;
 use crate::codec::UIntCodec;
 
@@ -23,16 +25,14 @@ impl sealed::AbiType for FixedBytesCodec {
 
 impl sealed::Encoder for FixedBytesCodec {
     fn encode_frame(&self, value: &Value) -> Result<Vec<u8>, Error> {
-        let bytes = value.as_bytes()?;
+        let bytes = value.as_fixed_bytes()?;
 
-        if bytes.len() < self.size {
+        if bytes.len() != self.size {
             return Err(Error::InvalidData);
         }
 
         let mut bytes = bytes.to_vec();
-
-        let align = 1 + self.size / 32;
-        bytes.resize(32 * align, 0);
+        bytes.resize(32, 0);
 
         Ok(bytes)
     }
@@ -40,13 +40,19 @@ impl sealed::Encoder for FixedBytesCodec {
 
 impl sealed::Decoder for FixedBytesCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
-        if frame.len() >= self.size {
-            Ok(Value::Bytes(frame[..self.size].to_vec()))
+        if frame.len() >= 32 {
+            Ok(Value::FixedBytes(frame[..self.size].to_vec()))
         } else {
             Err(Error::InvalidData)
-        } 
+        }
+    }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        layout.check(position, 32)?;
+        layout.push(position, section, Role::Value, path, sealed::AbiType::name(self), 32 - self.size);
+        Ok(())
     }
 }
 
@@ -61,7 +67,7 @@ impl sealed::Encoder for DynamicBytesCodec {
     fn encode_frame(&self, value: &Value) -> Result<Vec<u8>, Error> {
         let bytes = value.as_bytes()?;
 
-        let align = 1 + bytes.len() / 32;
+        let align = bytes.len().div_ceil(32);
         let mut buff = Vec::with_capacity(32 + 32 * align);
 
         let length = Value::UInt(bytes.len().into());
@@ -76,10 +82,10 @@ impl sealed::Encoder for DynamicBytesCodec {
 
 impl sealed::Decoder for DynamicBytesCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
         let head = UIntCodec::new(256).decode_frame(frame, 0)?;
         let head = head.as_uint().expect("head is uint");
-        let length = head.to_usize().unwrap();
+        let length = head.to_usize().ok_or(Error::InvalidData)?;
 
         let frame = &frame[32..];
 
@@ -90,6 +96,18 @@ impl sealed::Decoder for DynamicBytesCodec {
         let bytes = frame[..length].to_vec();
         Ok(Value::Bytes(bytes))
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        let length = layout.read_usize(position)?;
+        layout.check(position + 32, length)?;
+        layout.push(position, section, Role::Length(length), path, sealed::AbiType::name(self), 0);
+
+        for chunk in 0..length.div_ceil(32) {
+            let padding = 32 * (chunk + 1) - length.max(32 * chunk).min(32 * (chunk + 1));
+            layout.push(position + 32 * (chunk + 1), section, Role::Data, path, sealed::AbiType::name(self), padding);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -102,8 +120,16 @@ mod tests {
         let bytes = hex::decode("FEEDFACE00000000000000000000000000000000000000000000000000000000").unwrap();
         assert_eq!(
             bytes,
-            FixedBytesCodec::new(4).encode(&Value::Bytes(vec![0xFE, 0xED, 0xFA, 0xCE])).unwrap(),
+            FixedBytesCodec::new(4).encode(&Value::FixedBytes(vec![0xFE, 0xED, 0xFA, 0xCE])).unwrap(),
         );
+
+        let hash = hex::decode("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef").unwrap();
+        assert_eq!(hash, FixedBytesCodec::new(32).encode(&Value::FixedBytes(hash.clone())).unwrap());
+        assert_eq!(Value::FixedBytes(hash.clone()), FixedBytesCodec::new(32).decode(&hash).unwrap());
+
+        assert_eq!(FixedBytesCodec::new(4).encode(&Value::FixedBytes(vec![0xFE, 0xED])), Err(Error::InvalidData));
+        assert_eq!(FixedBytesCodec::new(4).encode(&Value::FixedBytes(vec![0; 5])), Err(Error::InvalidData));
+        assert_eq!(FixedBytesCodec::new(4).encode(&Value::Bytes(vec![0; 4])), Err(Error::InvalidData));
     }
 
     #[test]
@@ -111,25 +137,35 @@ mod tests {
         let bytes = hex::decode(concat!(
             "0000000000000000000000000000000000000000000000000000000000000008",
             "FEEDFACEFEEDFACE000000000000000000000000000000000000000000000000",
-       )).unwrap();
+        )).unwrap();
 
         assert_eq!(
             bytes,
             DynamicBytesCodec.encode(&Value::Bytes(vec![0xFE, 0xED, 0xFA, 0xCE, 0xFE, 0xED, 0xFA, 0xCE])).unwrap(),
         );
+
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "FEEDFACEFEEDFACEFEEDFACEFEEDFACEFEEDFACEFEEDFACEFEEDFACEFEEDFACE",
+        )).unwrap();
+
+        assert_eq!(
+            bytes,
+            DynamicBytesCodec.encode(&Value::Bytes(hex::decode("FEEDFACE".repeat(8)).unwrap())).unwrap(),
+        );
     }
 
     #[test]
     fn test_fixed_bytes_decoder() {
         let bytes = hex::decode("FEEDFACE00000000000000000000000000000000000000000000000000000000").unwrap();
         assert_eq!(
-            Value::Bytes(hex::decode("FEEDFACE").unwrap()),
+            Value::FixedBytes(hex::decode("FEEDFACE").unwrap()),
             FixedBytesCodec::new(4).decode(&bytes).unwrap(),
         );
 
         let bytes = hex::decode("DEADC0DEFEEDFACE000000000000000000000000000000000000000000000000").unwrap();
         assert_eq!(
-            Value::Bytes(hex::decode("DEADC0DEFEEDFACE").unwrap()),
+            Value::FixedBytes(hex::decode("DEADC0DEFEEDFACE").unwrap()),
             FixedBytesCodec::new(8).decode(&bytes).unwrap(),
         );
     }
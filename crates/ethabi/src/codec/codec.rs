@@ -1,8 +1,12 @@
+use crate::prelude::*;
 use crate::{Value, Error};
+use crate::layout::Layout;
 
 pub(crate) mod sealed {
     use super::Value;
     use super::Error;
+    use crate::prelude::*;
+    use crate::layout::{Layout, Section};
 
     pub trait AbiType {
         fn name(&self) -> &str;
@@ -15,6 +19,7 @@ pub(crate) mod sealed {
 
     pub trait Decoder: AbiType {
         fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error>;
+        fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error>;
     }
 }
 
@@ -28,10 +33,19 @@ pub trait Decoder: sealed::Decoder {
     fn decode(&self, bytes: &[u8]) -> Result<Value, Error> {
         self.decode_frame(bytes, 0)
     }
+
+    fn annotate(&self, bytes: &[u8]) -> Result<Layout, Error> {
+        Layout::annotate(self, bytes)
+    }
 }
 
-pub trait Codec: Encoder + Decoder {}
+pub trait Codec: Encoder + Decoder + Send + Sync {
+    fn encode_annotated(&self, value: &Value) -> Result<Layout, Error> {
+        let bytes = self.encode(value)?;
+        self.annotate(&bytes)
+    }
+}
 
 impl<T: sealed::Decoder> Decoder for T {}
 impl<T: sealed::Encoder> Encoder for T {}
-impl<T: Encoder + Decoder> Codec for T {}
+impl<T: Encoder + Decoder + Send + Sync> Codec for T {}
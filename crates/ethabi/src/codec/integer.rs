@@ -1,6 +1,9 @@
 use num_bigint::{BigInt, BigUint};
+use num_traits::Signed;
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{Layout, Role, Section};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct IntCodec {
@@ -41,17 +44,21 @@ impl sealed::AbiType for UIntCodec {
 impl sealed::Encoder for IntCodec {
     fn encode_frame(&self, value: &Value) -> Result<Vec<u8>, Error> {
         let value = value.as_int()?;
-        let value = value % BigInt::from(2_u32).pow(self.size as u32);
+        let bound = BigInt::from(2_u32).pow(self.size as u32 - 1);
+        if *value >= bound || *value < -bound {
+            return Err(Error::InvalidData);
+        }
 
-        let (_, bytes) = value.to_bytes_be();
-        let bytes = std::iter::repeat(0).take(32 - bytes.len()).chain(bytes).collect();
+        let bytes = value.to_signed_bytes_be();
+        let sign = if value.is_negative() { 0xff } else { 0x00 };
+        let bytes = core::iter::repeat_n(sign, 32 - bytes.len()).chain(bytes).collect();
         Ok(bytes)
     }
 }
 
 impl sealed::Decoder for IntCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
         if frame.len() < 32 {
             return Err(Error::InvalidData)
@@ -61,6 +68,12 @@ impl sealed::Decoder for IntCodec {
         let value = Value::Int(BigInt::from_signed_bytes_be(&frame[begin..32]));
         Ok(value)
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        layout.check(position, 32)?;
+        layout.push(position, section, Role::Value, path, sealed::AbiType::name(self), 32 - self.size / 8);
+        Ok(())
+    }
 }
 
 impl sealed::Encoder for UIntCodec {
@@ -69,14 +82,14 @@ impl sealed::Encoder for UIntCodec {
         let value = value % BigUint::from(2_u32).pow(self.size as u32);
 
         let bytes = value.to_bytes_be();
-        let value = std::iter::repeat(0).take(32 - bytes.len()).chain(bytes).collect();
+        let value = core::iter::repeat_n(0, 32 - bytes.len()).chain(bytes).collect();
         Ok(value)
     }
 }
 
 impl sealed::Decoder for UIntCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
         if frame.len() < 32 {
             return Err(Error::InvalidData)
@@ -86,6 +99,12 @@ impl sealed::Decoder for UIntCodec {
         let value = Value::UInt(BigUint::from_bytes_be(&frame[begin..32]));
         Ok(value)
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        layout.check(position, 32)?;
+        layout.push(position, section, Role::Value, path, sealed::AbiType::name(self), 32 - self.size / 8);
+        Ok(())
+    }
 }
 
 
@@ -177,5 +196,26 @@ mod tests {
             bytes,
             IntCodec::new(256).encode(&Value::Int(0xFEEDFACE_u32.into())).unwrap(),
         );
+
+        let bytes = hex::decode("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE").unwrap();
+        assert_eq!(
+            bytes,
+            IntCodec::new(256).encode(&Value::Int(BigInt::from(-2))).unwrap(),
+        );
+
+        let bytes = hex::decode("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF80").unwrap();
+        assert_eq!(
+            bytes,
+            IntCodec::new(8).encode(&Value::Int(BigInt::from(-128))).unwrap(),
+        );
+
+        let bytes = hex::decode("000000000000000000000000000000000000000000000000000000000000007F").unwrap();
+        assert_eq!(
+            bytes,
+            IntCodec::new(8).encode(&Value::Int(BigInt::from(127))).unwrap(),
+        );
+
+        assert_eq!(IntCodec::new(8).encode(&Value::Int(BigInt::from(128))), Err(Error::InvalidData));
+        assert_eq!(IntCodec::new(8).encode(&Value::Int(BigInt::from(-129))), Err(Error::InvalidData));
     }
 }
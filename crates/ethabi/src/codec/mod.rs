@@ -10,6 +10,7 @@ pub(crate) use integer::{IntCodec, UIntCodec};
 pub(crate) use string::StringCodec;
 pub(crate) use tuple::TupleCodec;
 
+#[allow(clippy::module_inception)]
 mod codec;
 mod address;
 mod array;
@@ -1,6 +1,8 @@
 use num_traits::ToPrimitive;
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{Layout, Role, Section};
 use crate::codec::UIntCodec;
 
 use crate::codec::{Encoder, Decoder};
@@ -16,7 +18,7 @@ impl sealed::Encoder for StringCodec {
     fn encode_frame(&self, value: &Value) -> Result<Vec<u8>, Error> {
         let string = value.as_string()?;
 
-        let align = 1 + string.len() / 32;
+        let align = string.len().div_ceil(32);
         let mut buff = Vec::with_capacity(32 + 32 * align);
 
         let length = Value::UInt(string.len().into());
@@ -30,11 +32,11 @@ impl sealed::Encoder for StringCodec {
 
 impl sealed::Decoder for StringCodec {
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
         let head = UIntCodec::new(256).decode(frame)?;
         let head = head.as_uint()?;
-        let length = head.to_usize().unwrap();
+        let length = head.to_usize().ok_or(Error::InvalidData)?;
 
         let frame = &frame[32..];
 
@@ -45,6 +47,18 @@ impl sealed::Decoder for StringCodec {
         let value = Value::String(String::from_utf8_lossy(&frame[..length]).to_string());
         Ok(value)
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        let length = layout.read_usize(position)?;
+        layout.check(position + 32, length)?;
+        layout.push(position, section, Role::Length(length), path, sealed::AbiType::name(self), 0);
+
+        for chunk in 0..length.div_ceil(32) {
+            let padding = 32 * (chunk + 1) - length.max(32 * chunk).min(32 * (chunk + 1));
+            layout.push(position + 32 * (chunk + 1), section, Role::Data, path, sealed::AbiType::name(self), padding);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,8 @@
 use num_traits::ToPrimitive;
+use crate::prelude::*;
 use crate::codec::sealed;
 use crate::{Value, Error};
+use crate::layout::{annotate_element, Layout, Section};
 use crate::codec::{Codec, Encoder};
 use crate::codec::UIntCodec;
 
@@ -43,7 +45,7 @@ impl sealed::Encoder for TupleCodec {
         }
 
         let head_size: usize = head_chunks.iter().flat_map(|item| item.as_ref().map(|head| head.len()).or(Some(32))).sum();
-        let mut tail_offset = std::iter::once(0).chain(tail_chunks.iter().scan(0, |offset, chunk| {
+        let mut tail_offset = core::iter::once(0).chain(tail_chunks.iter().scan(0, |offset, chunk| {
             *offset += chunk.len();
             Some(*offset)
         }));
@@ -71,7 +73,7 @@ impl sealed::Encoder for TupleCodec {
 impl sealed::Decoder for TupleCodec {
 
     fn decode_frame(&self, bytes: &[u8], offset: usize) -> Result<Value, Error> {
-        let frame = &bytes[offset..];
+        let frame = bytes.get(offset..).ok_or(Error::InvalidData)?;
 
         let mut values = Vec::new();
         for (index, codec) in self.codecs.iter().enumerate() {
@@ -80,7 +82,7 @@ impl sealed::Decoder for TupleCodec {
             let value = if codec.is_dynamic() {
                 let head = UIntCodec::new(256).decode_frame(frame, offset)?;
                 let head = head.as_uint()?;
-                let frame_base = head.to_usize().unwrap();
+                let frame_base = head.to_usize().ok_or(Error::InvalidData)?;
                 codec.decode_frame(frame, frame_base)?
             } else {
                 codec.decode_frame(frame, 32 * index)?
@@ -92,6 +94,14 @@ impl sealed::Decoder for TupleCodec {
         let value = Value::Tuple(values);
         Ok(value)
     }
+
+    fn annotate_frame(&self, layout: &mut Layout, position: usize, path: &str, section: Section) -> Result<(), Error> {
+        for (index, codec) in self.codecs.iter().enumerate() {
+            let path = format!("{}.{}", path, index);
+            annotate_element(layout, codec.as_ref(), position, index, &path, section)?;
+        }
+        Ok(())
+    }
 }
 
 
@@ -223,7 +233,7 @@ mod tests {
                     Value::UInt(0x456_u32.into()),
                     Value::UInt(0x789_u32.into()),
                 ]),
-                Value::Bytes("1234567890".as_bytes().to_vec()),
+                Value::FixedBytes("1234567890".as_bytes().to_vec()),
                 Value::Bytes("Hello, world!".as_bytes().to_vec()),
             ])).unwrap()
         );
@@ -358,4 +368,24 @@ mod tests {
             tuple_decoder.decode(&bytes).unwrap(),
         );
     }
+
+    #[test]
+    fn test_invalid_offset_decoder() {
+        let codec = TupleCodec::new(vec![Box::new(UIntCodec::new(256)), Box::new(StringCodec)]);
+
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "00000000000000000000000000000000000000000000000000000000000000c0",
+        )).unwrap();
+        assert_eq!(codec.decode(&bytes), Err(Error::InvalidData));
+
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        )).unwrap();
+        assert_eq!(codec.decode(&bytes), Err(Error::InvalidData));
+
+        let bytes = hex::decode("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
+        assert_eq!(codec.decode(&bytes), Err(Error::InvalidData));
+    }
 }
@@ -1,12 +1,33 @@
+use crate::prelude::*;
 
-#[derive(Clone, Debug, Error, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum Error {
-    #[error("Input data is invalid")]
+    #[cfg_attr(feature = "std", error("Input data is invalid"))]
     InvalidData,
 
-    #[error("Hex decoding error : {0}")]
-    Hex(#[from] hex::FromHexError),
+    #[cfg_attr(feature = "std", error("Hex decoding error : {0}"))]
+    Hex(#[cfg_attr(feature = "std", from)] hex::FromHexError),
 
-    #[error("Unknown type : {0}")]
+    #[cfg_attr(feature = "std", error("Unknown type : {0}"))]
     UnknownType(String),
 }
+
+// Without `std` there is no `thiserror`; keep the same messages and conversions.
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidData => write!(f, "Input data is invalid"),
+            Error::Hex(error) => write!(f, "Hex decoding error : {}", error),
+            Error::UnknownType(name) => write!(f, "Unknown type : {}", name),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<hex::FromHexError> for Error {
+    fn from(error: hex::FromHexError) -> Self {
+        Error::Hex(error)
+    }
+}
@@ -0,0 +1,338 @@
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use crate::prelude::*;
+use crate::codec::{
+    AddressCodec,
+    DynamicArrayCodec,
+    DynamicBytesCodec,
+    FixedBytesCodec,
+    IntCodec,
+    StringCodec,
+    TupleCodec,
+    UIntCodec,
+};
+use crate::Codec;
+use crate::Error;
+
+const MAX_DEPTH: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Guess {
+    Zero,
+    UInt,
+    Int,
+    Address,
+    Word,
+    Bytes,
+    String,
+    Array(Box<Guess>),
+    Tuple(Vec<Guess>),
+}
+
+impl Guess {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Guess::Bytes | Guess::String | Guess::Array(_) => true,
+            Guess::Tuple(guesses) => guesses.iter().any(Guess::is_dynamic),
+            _ => false,
+        }
+    }
+
+    fn merge(self, other: Guess) -> Option<Guess> {
+        match (self, other) {
+            (Guess::Zero, other) | (other, Guess::Zero) => Some(other),
+            (this, other) if this == other => Some(this),
+            (Guess::UInt, Guess::Address) | (Guess::Address, Guess::UInt) => Some(Guess::UInt),
+            (this, other) if !this.is_dynamic() && !other.is_dynamic() => Some(Guess::Word),
+            _ => None,
+        }
+    }
+
+    fn codec(&self) -> Box<dyn Codec> {
+        match self {
+            Guess::Zero | Guess::UInt => Box::new(UIntCodec::new(256)),
+            Guess::Int => Box::new(IntCodec::new(256)),
+            Guess::Address => Box::new(AddressCodec),
+            Guess::Word => Box::new(FixedBytesCodec::new(32)),
+            Guess::Bytes => Box::new(DynamicBytesCodec),
+            Guess::String => Box::new(StringCodec),
+            Guess::Array(guess) => Box::new(DynamicArrayCodec::new(guess.codec())),
+            Guess::Tuple(guesses) => Box::new(TupleCodec::new(guesses.iter().map(Guess::codec).collect())),
+        }
+    }
+}
+
+fn word(region: &[u8], at: usize) -> Option<&[u8]> {
+    region.get(at..at.checked_add(32)?)
+}
+
+fn word_as_usize(word: &[u8]) -> Option<usize> {
+    BigUint::from_bytes_be(word).to_usize()
+}
+
+fn classify(word: &[u8]) -> Guess {
+    let leading_zeros = word.iter().take_while(|&&byte| byte == 0x00).count();
+    let leading_ones = word.iter().take_while(|&&byte| byte == 0xff).count();
+
+    match leading_zeros {
+        32 => Guess::Zero,
+        12..=15 => Guess::Address,
+        16.. => Guess::UInt,
+        0 if leading_ones >= 16 => Guess::Int,
+        0 => Guess::Word,
+        _ => Guess::UInt,
+    }
+}
+
+// Decodes `region[at..]` with the guess and re-encodes it; the guess only holds if the
+// bytes come back identical. Returns how many bytes the guess accounts for.
+fn consumed(guess: &Guess, region: &[u8], at: usize) -> Option<usize> {
+    let frame = region.get(at..)?;
+    let codec = guess.codec();
+    let value = codec.decode(frame).ok()?;
+    let encoded = codec.encode(&value).ok()?;
+
+    match frame.starts_with(&encoded) {
+        true => Some(encoded.len()),
+        false => None,
+    }
+}
+
+fn infer_bytes(region: &[u8], at: usize, length: usize) -> Option<Guess> {
+    let data = region.get(at + 32..)?.get(..length)?;
+    let guess = match core::str::from_utf8(data) {
+        Ok(text) if !text.is_empty() && text.chars().all(|c| !c.is_control() || c.is_whitespace()) => Guess::String,
+        _ => Guess::Bytes,
+    };
+    Some(guess)
+}
+
+fn infer_array(region: &[u8], at: usize, length: usize, depth: usize) -> Vec<Guess> {
+    let mut guesses = Vec::new();
+    let elements = match region.get(at + 32..) {
+        Some(elements) if length > 0 && length <= elements.len() / 32 => elements,
+        _ => return guesses,
+    };
+
+    let dynamic = (0..length)
+        .map(|index| {
+            let offset = word_as_usize(word(elements, 32 * index)?)?;
+            match offset % 32 == 0 && offset >= 32 * length {
+                true => infer_dynamic(elements, offset, depth + 1),
+                false => None,
+            }
+        })
+        .try_fold(Guess::Zero, |merged, guess| merged.merge(guess?));
+    if let Some(guess) = dynamic {
+        guesses.push(Guess::Array(Box::new(guess)));
+    }
+
+    let fixed = (0..length)
+        .map(|index| word(elements, 32 * index).map(classify))
+        .try_fold(Guess::Zero, |merged, guess| merged.merge(guess?));
+    if let Some(guess) = fixed {
+        guesses.push(Guess::Array(Box::new(guess)));
+    }
+
+    guesses
+}
+
+fn infer_dynamic(region: &[u8], at: usize, depth: usize) -> Option<Guess> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    let length = word_as_usize(word(region, at)?);
+
+    let mut candidates = Vec::new();
+    match length {
+        Some(0) => candidates.push(Guess::Bytes),
+        Some(length) => {
+            candidates.extend(infer_bytes(region, at, length));
+            candidates.extend(infer_array(region, at, length, depth));
+        }
+        None => {}
+    }
+
+    if let Some(guesses) = infer_tuple(&region[at..], depth + 1) {
+        let guess = Guess::Tuple(guesses);
+        if guess.is_dynamic() {
+            candidates.push(guess);
+        }
+    }
+
+    candidates.into_iter()
+        .filter_map(|guess| consumed(&guess, region, at).map(|consumed| (consumed, guess)))
+        .fold(None, |best: Option<(usize, Guess)>, (consumed, guess)| match best {
+            Some((best_consumed, _)) if best_consumed >= consumed => best,
+            _ => Some((consumed, guess)),
+        })
+        .map(|(_, guess)| guess)
+}
+
+fn infer_tuple(region: &[u8], depth: usize) -> Option<Vec<Guess>> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    let mut guesses = Vec::new();
+    let mut head_end = region.len();
+    let mut last_offset = 0;
+
+    let mut index = 0;
+    while 32 * index < head_end {
+        let head = word(region, 32 * index)?;
+        let dynamic = word_as_usize(head)
+            .filter(|&offset| offset % 32 == 0 && offset >= 32 * (index + 1) && offset > last_offset)
+            .and_then(|offset| infer_dynamic(region, offset, depth).map(|guess| (offset, guess)));
+
+        match dynamic {
+            Some((offset, guess)) => {
+                head_end = head_end.min(offset);
+                last_offset = offset;
+                guesses.push(guess);
+            }
+            None => guesses.push(classify(head)),
+        }
+        index += 1;
+    }
+
+    Some(guesses)
+}
+
+pub fn infer(bytes: &[u8]) -> Result<Box<dyn Codec>, Error> {
+    if !bytes.len().is_multiple_of(32) {
+        return Err(Error::InvalidData);
+    }
+
+    let guesses = infer_tuple(bytes, 0).ok_or(Error::InvalidData)?;
+    let codec = Guess::Tuple(guesses).codec();
+    codec.decode(bytes)?;
+    Ok(codec)
+}
+
+pub fn infer_calldata(calldata: &[u8]) -> Result<Box<dyn Codec>, Error> {
+    match calldata.get(4..) {
+        Some(bytes) => infer(bytes),
+        None => Err(Error::InvalidData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    #[test]
+    fn test_infer_static_words() {
+        let bytes = hex::decode(concat!(
+            "a9059cbb",
+            "000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface",
+            "00000000000000000000000000000000000000000000000000000000000003e8",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe",
+            "5553444300000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+
+        let codec = infer_calldata(&bytes).unwrap();
+        assert_eq!(codec.name(), "(address,uint256,uint256,int256,bytes32)");
+    }
+
+    #[test]
+    fn test_infer_string() {
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "6162630000000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+
+        let codec = infer(&bytes).unwrap();
+        assert_eq!(codec.name(), "(uint256,string)");
+        assert_eq!(
+            codec.decode(&bytes).unwrap(),
+            Value::Tuple(vec![
+                Value::UInt(1_u8.into()),
+                Value::String("abc".to_string()),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_infer_arrays() {
+        let bytes = hex::decode(concat!(
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000160",
+            "0000000000000000000000000000000000000000000000000000000000000220",
+            "0000000000000000000000000000000000000000000000000000000000000280",
+            "00000000000000000000000000000000000000000000000000000000000002e0",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000002222222222222222222222222222222222222222",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000002222222222222222222222222222222222222222",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000002222222222222222222222222222222222222222",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000014",
+            "0000000000000000000000000000000000000000000000000000000000000019",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000000"
+        )).unwrap();
+
+        let codec = infer(&bytes).unwrap();
+        assert_eq!(codec.name(), "(address[],uint256[],address[],uint256[],uint256[])");
+    }
+
+    #[test]
+    fn test_infer_dynamic_element_array() {
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000c5b6b5",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000080",
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "00000000000000000000000000caec2e118abc4c510440a8d1ac8565fec0180c",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "deadbeef00000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+
+        let codec = infer(&bytes).unwrap();
+        assert_eq!(codec.name(), "(uint256,bytes[])");
+    }
+
+    #[test]
+    fn test_infer_nested_tuple() {
+        // (uint, (uint, uint[]))
+        let bytes = hex::decode(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "0000000000000000000000000000000000000000000000000000000000000006",
+        )).unwrap();
+
+        let codec = infer(&bytes).unwrap();
+        assert_eq!(codec.name(), "(uint256,(uint256,uint256[]))");
+    }
+
+    #[test]
+    fn test_infer_misaligned() {
+        assert_eq!(infer(&[0x01, 0x02]).err(), Some(Error::InvalidData));
+        assert_eq!(infer_calldata(&[0x01, 0x02]).err(), Some(Error::InvalidData));
+        assert_eq!(infer(&[]).unwrap().name(), "()");
+    }
+}
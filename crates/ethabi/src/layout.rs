@@ -0,0 +1,236 @@
+use core::fmt;
+use num_traits::ToPrimitive;
+use crate::prelude::*;
+use crate::codec::{Codec, UIntCodec};
+use crate::codec::sealed::Decoder;
+use crate::Error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Section {
+    Head,
+    Tail,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Role {
+    Value,
+    // Absolute position the offset word points to.
+    Offset(usize),
+    Length(usize),
+    Data,
+    Unused,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Word {
+    pub position: usize,
+    pub section: Section,
+    pub role: Role,
+    pub path: String,
+    pub kind: String,
+    pub padding: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    bytes: Vec<u8>,
+    words: Vec<Word>,
+}
+
+impl Layout {
+    pub(crate) fn annotate<D: Decoder + ?Sized>(decoder: &D, bytes: &[u8]) -> Result<Self, Error> {
+        let mut layout = Self {
+            bytes: bytes.to_vec(),
+            words: Vec::new(),
+        };
+        decoder.annotate_frame(&mut layout, 0, "$", Section::Head)?;
+        layout.finish();
+        Ok(layout)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    pub fn word_bytes(&self, word: &Word) -> &[u8] {
+        let end = (word.position + 32).min(self.bytes.len());
+        &self.bytes[word.position..end]
+    }
+
+    pub(crate) fn check(&self, position: usize, length: usize) -> Result<(), Error> {
+        match position.checked_add(length) {
+            Some(end) if end <= self.bytes.len() => Ok(()),
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    pub(crate) fn read_usize(&self, position: usize) -> Result<usize, Error> {
+        let value = UIntCodec::new(256).decode_frame(&self.bytes, position)?;
+        value.as_uint()?.to_usize().ok_or(Error::InvalidData)
+    }
+
+    pub(crate) fn push(&mut self, position: usize, section: Section, role: Role, path: &str, kind: &str, padding: usize) {
+        self.words.push(Word {
+            position,
+            section,
+            role,
+            path: path.to_string(),
+            kind: kind.to_string(),
+            padding,
+        });
+    }
+
+    // Words are collected in decoding order; sort them by position and mark every
+    // 32 byte chunk that no codec looked at.
+    fn finish(&mut self) {
+        let mut covered = vec![false; self.bytes.len().div_ceil(32)];
+        for word in &self.words {
+            let first = word.position / 32;
+            let last = word.position.div_ceil(32);
+            for chunk in covered.iter_mut().take(last + 1).skip(first) {
+                *chunk = true;
+            }
+        }
+
+        for (index, _) in covered.iter().enumerate().filter(|(_, covered)| !**covered) {
+            self.push(32 * index, Section::Tail, Role::Unused, "", "", 0);
+        }
+        self.words.sort_by_key(|word| word.position);
+    }
+}
+
+// Annotates one element of a tuple or an array whose head starts at `frame`. Dynamic elements
+// occupy an offset word in the head and are annotated at the position it points to.
+pub(crate) fn annotate_element(
+    layout: &mut Layout,
+    codec: &dyn Codec,
+    frame: usize,
+    index: usize,
+    path: &str,
+    section: Section,
+) -> Result<(), Error> {
+    let position = frame + 32 * index;
+    if codec.is_dynamic() {
+        let offset = layout.read_usize(position)?;
+        let target = frame.checked_add(offset).ok_or(Error::InvalidData)?;
+        layout.push(position, section, Role::Offset(target), path, codec.name(), 0);
+        codec.annotate_frame(layout, target, path, Section::Tail)
+    } else {
+        codec.annotate_frame(layout, position, path, section)
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Head => write!(f, "head"),
+            Section::Tail => write!(f, "tail"),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Value => write!(f, "value"),
+            Role::Offset(target) => write!(f, "offset -> {:#06x}", target),
+            Role::Length(length) => write!(f, "length {}", length),
+            Role::Data => write!(f, "data"),
+            Role::Unused => write!(f, "unused"),
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path_width = self.words.iter().map(|word| word.path.len()).max().unwrap_or(0);
+        let kind_width = self.words.iter().map(|word| word.kind.len()).max().unwrap_or(0);
+
+        for word in &self.words {
+            let line = format!(
+                "{:#06x}  {:<64}  {}  {:<path_width$}  {:<kind_width$}  {}",
+                word.position,
+                hex::encode(self.word_bytes(word)),
+                word.section,
+                word.path,
+                word.kind,
+                word.role,
+            );
+            write!(f, "{}", line.trim_end())?;
+            if word.padding > 0 {
+                write!(f, " ({} bytes padding)", word.padding)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Value};
+
+    #[test]
+    fn test_annotate_encoding() {
+        let codec = parse(&["uint256", "(uint256,uint256[])", "string"]).unwrap();
+        let layout = codec.encode_annotated(&Value::Tuple(vec![
+            Value::UInt(1_u8.into()),
+            Value::Tuple(vec![
+                Value::UInt(2_u8.into()),
+                Value::Array(vec![Value::UInt(3_u8.into())]),
+            ]),
+            Value::String("abc".to_string()),
+        ])).unwrap();
+
+        let roles = layout.words().iter()
+            .map(|word| (word.position, word.section, word.role, word.path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(roles, vec![
+            (0x000, Section::Head, Role::Value, "$.0"),
+            (0x020, Section::Head, Role::Offset(0x60), "$.1"),
+            (0x040, Section::Head, Role::Offset(0xe0), "$.2"),
+            (0x060, Section::Tail, Role::Value, "$.1.0"),
+            (0x080, Section::Tail, Role::Offset(0xa0), "$.1.1"),
+            (0x0a0, Section::Tail, Role::Length(1), "$.1.1"),
+            (0x0c0, Section::Tail, Role::Value, "$.1.1[0]"),
+            (0x0e0, Section::Tail, Role::Length(3), "$.2"),
+            (0x100, Section::Tail, Role::Data, "$.2"),
+        ]);
+        assert_eq!(layout.words()[8].padding, 29);
+
+        let mut bytes = layout.bytes().to_vec();
+        bytes.extend([0; 32]);
+        let layout = codec.annotate(&bytes).unwrap();
+        assert_eq!(layout.words().last().unwrap().role, Role::Unused);
+    }
+
+    #[test]
+    fn test_display() {
+        let codec = parse(&["address", "bytes"]).unwrap();
+        let bytes = hex::decode(concat!(
+            "000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "abcd000000000000000000000000000000000000000000000000000000000000",
+        )).unwrap();
+
+        assert_eq!(codec.annotate(&bytes).unwrap().to_string(), concat!(
+            "0x0000  000000000000000000000000feedfacefeedfacefeedfacefeedfacefeedface  head  $.0  address  value (12 bytes padding)\n",
+            "0x0020  0000000000000000000000000000000000000000000000000000000000000040  head  $.1  bytes    offset -> 0x0040\n",
+            "0x0040  0000000000000000000000000000000000000000000000000000000000000002  tail  $.1  bytes    length 2\n",
+            "0x0060  abcd000000000000000000000000000000000000000000000000000000000000  tail  $.1  bytes    data (30 bytes padding)\n",
+        ));
+    }
+
+    #[test]
+    fn test_annotate_invalid_offset() {
+        let codec = parse(&["bytes"]).unwrap();
+        let bytes = hex::decode("00000000000000000000000000000000000000000000000000000000000000ff").unwrap();
+        assert_eq!(codec.annotate(&bytes), Err(Error::InvalidData));
+    }
+}
@@ -1,3 +1,6 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 pub extern crate num_bigint;
 pub extern crate num_traits;
 
@@ -5,16 +8,34 @@ extern crate hex;
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate thiserror;
 
 pub use codec::Codec;
+#[cfg(feature = "std")]
+pub use cache::CodecCache;
 pub use error::Error;
 pub use value::Value;
 pub use parser::parse;
+pub use infer::{infer, infer_calldata};
+pub use layout::{Layout, Role, Section, Word};
 
+#[cfg(feature = "std")]
+mod cache;
 mod codec;
 mod error;
+mod infer;
+mod layout;
 mod parser;
 mod grammar;
 mod value;
+
+// Collections and macros from `alloc`, so that the crate builds the same with and without `std`.
+mod prelude {
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
@@ -1,4 +1,5 @@
 use pest::Parser;
+use crate::prelude::*;
 use crate::codec::{
     AddressCodec,
     BooleanCodec,
@@ -72,9 +73,7 @@ impl<'v> EthAbiParser<'v> {
                 Rule::ConstArray => {
                     let digits = pair.into_inner().next()
                         .expect("Rule::ConstArray should have an inner: Rule::Digits");
-                    let size = digits.as_str().parse::<usize>()
-                        .expect("Rule::Digits should be a number");
-                    
+                    let size = parse_digits(digits.as_str())?;
                     Box::new(FixedArrayCodec::new(size, codec))
                 }
                 _ => unreachable!("Rule::Array can not expand to {:?}", rule),
@@ -86,6 +85,7 @@ impl<'v> EthAbiParser<'v> {
     }
 
     fn accept_basic_type(&self, pair: pest::iterators::Pair<Rule>) -> Result<Box<dyn Codec>, Error> {
+        let pair_str = pair.as_str().to_string();
         let mut inner = pair.into_inner();
 
         let base = inner.next().expect("Rule::BasicType should have an inner: Rule::BaseType");
@@ -105,21 +105,24 @@ impl<'v> EthAbiParser<'v> {
             "address" => Box::new(AddressCodec),
             "bool" => Box::new(BooleanCodec),
             "bytes" => {
-                let size = sub.map(|digits| digits.as_str().parse::<usize>().expect("Rule::Digits should be a number"));
+                let size = sub.map(|digits| parse_digits(digits.as_str())).transpose()?;
                 match size {
+                    Some(size) if size > 32 => Err(Error::UnknownType(format!("bytes{}", size)))?,
                     Some(size) => Box::new(FixedBytesCodec::new(size)),
                     None => Box::new(DynamicBytesCodec),
                 }
             }
+            // An old alias of bytes1.
+            "byte" if sub.is_none() => Box::new(FixedBytesCodec::new(1)),
             "int" => {
-                let size = sub.map(|digits| digits.as_str().parse::<usize>().expect("Rule::Digits should be a number")).unwrap_or(256);
+                let size = sub.map(|digits| parse_digits(digits.as_str())).transpose()?.unwrap_or(256);
                 if size > 256 || size % 8 != 0 {
                     Err(Error::UnknownType(format!("int{}", size)))?
                 }
                 Box::new(IntCodec::new(size))
             }
             "uint" => {
-                let size = sub.map(|digits| digits.as_str().parse::<usize>().expect("Rule::Digits should be a number")).unwrap_or(256);
+                let size = sub.map(|digits| parse_digits(digits.as_str())).transpose()?.unwrap_or(256);
                 if size > 256 || size % 8 != 0 {
                     Err(Error::UnknownType(format!("uint{}", size)))?
                 }
@@ -128,9 +131,7 @@ impl<'v> EthAbiParser<'v> {
             "string" => {
                 Box::new(StringCodec)
             }
-            "fixed" => unimplemented!("fixed type is not supported yet"),
-            "ufixed" => unimplemented!("ufixed type is not supported yet"),
-            "function" => unimplemented!("function type is not supported yet"),
+            "fixed" | "ufixed" | "function" => Err(Error::UnknownType(pair_str))?,
             _ => Err(Error::UnknownType(base_name.to_string()))?,
         };
 
@@ -141,12 +142,20 @@ impl<'v> EthAbiParser<'v> {
     }
 
     fn parse(&self, abi: &str) -> Result<Box<dyn Codec>, Error> {
-        let mut pairs = EthAbi::parse(Rule::Type, abi).unwrap();
+        let mut pairs = EthAbi::parse(Rule::Type, abi)
+            .map_err(|_| Error::UnknownType(abi.to_string()))?;
         let pair = pairs.next().expect("should have a pair");
+        if pair.as_str().len() != abi.trim_end().len() {
+            return Err(Error::UnknownType(abi.to_string()));
+        }
         self.accept_type(pair)
     }
 }
 
+fn parse_digits(digits: &str) -> Result<usize, Error> {
+    digits.parse::<usize>().map_err(|_| Error::UnknownType(digits.to_string()))
+}
+
 struct Visitor;
 
 impl Visitor {
@@ -182,6 +191,20 @@ mod tests {
         assert_eq!(codec.err(), Some(Error::UnknownType(abi.to_string())));
     }
 
+    #[test]
+    fn test_malformed_type() {
+        for abi in ["uint256)", "(uint256", "bytes33", "uint99999999999999999999999", "fixed128x18", "function"] {
+            assert!(matches!(parse(&[abi]), Err(Error::UnknownType(_))), "{}", abi);
+        }
+    }
+
+    #[test]
+    fn test_type_aliases() {
+        let codec = parse(&["uint", "int[]", "byte", "byte[2]"]).unwrap();
+        assert_eq!(codec.name(), "(uint256,int256[],bytes1,bytes1[2])");
+        assert!(matches!(parse(&["byte1"]), Err(Error::UnknownType(_))));
+    }
+
     #[test]
     fn test_simple_tuple_codec() {
         let abi = &["bool", "uint256"];
@@ -283,7 +306,7 @@ mod tests {
                     Value::UInt(0x456_u32.into()),
                     Value::UInt(0x789_u32.into()),
                 ]),
-                Value::Bytes("1234567890".as_bytes().to_vec()),
+                Value::FixedBytes("1234567890".as_bytes().to_vec()),
                 Value::Bytes("Hello, world!".as_bytes().to_vec()),
             ])).unwrap()
         );
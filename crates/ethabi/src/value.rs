@@ -1,4 +1,5 @@
 use num_bigint::{BigInt, BigUint};
+use crate::prelude::*;
 use crate::Error;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -7,6 +8,7 @@ pub enum Value {
     Boolean(bool),
     Int(BigInt),
     UInt(BigUint),
+    FixedBytes(Vec<u8>),
     Bytes(Vec<u8>),
     String(String),
     Array(Vec<Value>),
@@ -31,7 +33,7 @@ impl Value {
         match self {
             Value::Address(address) => {
                 let hex_address = hex::encode(address);
-                Ok(String::from(["0x", hex_address.as_str()].concat()))
+                Ok(["0x", hex_address.as_str()].concat())
             }
             _ => Err(Error::InvalidData),
         }
@@ -51,6 +53,13 @@ impl Value {
         }
     }
 
+    pub fn as_fixed_bytes(&self) -> Result<&[u8], Error> {
+        match self {
+            Value::FixedBytes(bytes) => Ok(bytes),
+            _ => Err(Error::InvalidData),
+        }
+    }
+
     pub fn as_bytes(&self) -> Result<&[u8], Error> {
         match self {
             Value::Bytes(bytes) => Ok(bytes),
@@ -0,0 +1,322 @@
+use num_bigint::BigUint;
+use crate::{Error, Item};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub list: bool,
+    // Size of the prefix in bytes; zero for a single byte below 0x80.
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl Header {
+    // Reads and validates the prefix only; the payload does not have to be available yet.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let prefix = *bytes.first().ok_or(Error::UnexpectedEof)?;
+
+        let header = match prefix {
+            0x00..=0x7f => Header { list: false, offset: 0, length: 1 },
+            0x80..=0xb7 => {
+                let length = (prefix - 0x80) as usize;
+                if length == 1 && *bytes.get(1).ok_or(Error::UnexpectedEof)? < 0x80 {
+                    return Err(Error::NonCanonicalSingleByte);
+                }
+                Header { list: false, offset: 1, length }
+            }
+            0xb8..=0xbf => long_header(bytes, false, (prefix - 0xb7) as usize)?,
+            0xc0..=0xf7 => Header { list: true, offset: 1, length: (prefix - 0xc0) as usize },
+            0xf8..=0xff => long_header(bytes, true, (prefix - 0xf7) as usize)?,
+        };
+
+        header.offset.checked_add(header.length).ok_or(Error::Overflow)?;
+        Ok(header)
+    }
+
+    pub fn total(&self) -> usize {
+        self.offset + self.length
+    }
+}
+
+fn long_header(bytes: &[u8], list: bool, size: usize) -> Result<Header, Error> {
+    let length = bytes.get(1..1 + size).ok_or(Error::UnexpectedEof)?;
+    if length[0] == 0 {
+        return Err(Error::NonCanonicalSize);
+    }
+    if size > std::mem::size_of::<usize>() {
+        return Err(Error::Overflow);
+    }
+
+    let length = length.iter().fold(0_usize, |length, byte| length << 8 | *byte as usize);
+    if length < 56 {
+        return Err(Error::NonCanonicalSize);
+    }
+
+    Ok(Header { list, offset: 1 + size, length })
+}
+
+pub trait Decodable: Sized {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error>;
+}
+
+// Decodes a single item that must span the whole input.
+pub fn decode<T: Decodable>(bytes: &[u8]) -> Result<T, Error> {
+    let mut decoder = Decoder::new(bytes);
+    let value = decoder.decode()?;
+    decoder.finish()?;
+    Ok(value)
+}
+
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn header(&self) -> Result<Header, Error> {
+        Header::decode(self.bytes)
+    }
+
+    fn take(&mut self) -> Result<(Header, &'a [u8]), Error> {
+        let header = self.header()?;
+        if self.bytes.len() < header.total() {
+            return Err(Error::UnexpectedEof);
+        }
+
+        let payload = &self.bytes[header.offset..header.total()];
+        self.bytes = &self.bytes[header.total()..];
+        Ok((header, payload))
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        match self.take()? {
+            (header, _) if header.list => Err(Error::UnexpectedList),
+            (_, payload) => Ok(payload),
+        }
+    }
+
+    pub fn list(&mut self) -> Result<Decoder<'a>, Error> {
+        match self.take()? {
+            (header, payload) if header.list => Ok(Decoder::new(payload)),
+            _ => Err(Error::UnexpectedString),
+        }
+    }
+
+    pub fn decode<T: Decodable>(&mut self) -> Result<T, Error> {
+        T::decode(self)
+    }
+
+    pub fn decode_list<T: Decodable>(&mut self) -> Result<Vec<T>, Error> {
+        let mut list = self.list()?;
+        let mut values = Vec::new();
+        while !list.is_empty() {
+            values.push(list.decode()?);
+        }
+        Ok(values)
+    }
+
+    pub fn finish(self) -> Result<(), Error> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(Error::TrailingBytes),
+        }
+    }
+}
+
+// Buffers incoming bytes and yields items once they are complete.
+#[derive(Clone, Debug, Default)]
+pub struct Stream {
+    buffer: Vec<u8>,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn next_item(&mut self) -> Result<Option<Item>, Error> {
+        self.decode_next()
+    }
+
+    pub fn decode_next<T: Decodable>(&mut self) -> Result<Option<T>, Error> {
+        let header = match Header::decode(&self.buffer) {
+            Ok(header) => header,
+            Err(Error::UnexpectedEof) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        if self.buffer.len() < header.total() {
+            return Ok(None);
+        }
+
+        let value = decode(&self.buffer[..header.total()])?;
+        self.buffer.drain(..header.total());
+        Ok(Some(value))
+    }
+}
+
+fn decode_uint<'a>(decoder: &mut Decoder<'a>, size: usize) -> Result<&'a [u8], Error> {
+    let bytes = decoder.bytes()?;
+    if bytes.first() == Some(&0) {
+        return Err(Error::LeadingZero);
+    }
+    if bytes.len() > size {
+        return Err(Error::Overflow);
+    }
+    Ok(bytes)
+}
+
+macro_rules! impl_uint {
+    ($($ty:ty),*) => {$(
+        impl Decodable for $ty {
+            fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+                let bytes = decode_uint(decoder, std::mem::size_of::<$ty>())?;
+                let mut buffer = [0; std::mem::size_of::<$ty>()];
+                buffer[std::mem::size_of::<$ty>() - bytes.len()..].copy_from_slice(bytes);
+                Ok(<$ty>::from_be_bytes(buffer))
+            }
+        }
+    )*};
+}
+
+impl_uint!(u8, u16, u32, u64, u128, usize);
+
+impl Decodable for BigUint {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        let bytes = decode_uint(decoder, usize::MAX)?;
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+}
+
+impl Decodable for bool {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        match decoder.decode::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+impl Decodable for Vec<u8> {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        Ok(decoder.bytes()?.to_vec())
+    }
+}
+
+impl<const N: usize> Decodable for [u8; N] {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        decoder.bytes()?.try_into().map_err(|_| Error::InvalidData)
+    }
+}
+
+impl Decodable for String {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        String::from_utf8(decoder.bytes()?.to_vec()).map_err(|_| Error::InvalidData)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: Decodable),+> Decodable for ($($name,)+) {
+            fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+                let mut list = decoder.list()?;
+                let value = ($(list.decode::<$name>()?,)+);
+                list.finish()?;
+                Ok(value)
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A B);
+impl_tuple!(A B C);
+impl_tuple!(A B C D);
+impl_tuple!(A B C D E);
+impl_tuple!(A B C D E F);
+impl_tuple!(A B C D E F G);
+impl_tuple!(A B C D E F G H);
+impl_tuple!(A B C D E F G H I);
+impl_tuple!(A B C D E F G H I J);
+impl_tuple!(A B C D E F G H I J K);
+impl_tuple!(A B C D E F G H I J K L);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, encode_list};
+
+    #[test]
+    fn test_decode_integers() {
+        assert_eq!(decode::<u64>(&[0x80]), Ok(0));
+        assert_eq!(decode::<u16>(&[0x82, 0x04, 0x00]), Ok(0x0400));
+        assert_eq!(decode::<u8>(&[0x82, 0x04, 0x00]), Err(Error::Overflow));
+        assert_eq!(decode::<u64>(&[0x00]), Err(Error::LeadingZero));
+        assert_eq!(decode::<u64>(&[0x82, 0x00, 0x01]), Err(Error::LeadingZero));
+        assert_eq!(decode::<BigUint>(&[0x82, 0x04, 0x00]), Ok(BigUint::from(1024_u32)));
+        assert_eq!(decode::<bool>(&[0x02]), Err(Error::InvalidData));
+    }
+
+    #[test]
+    fn test_decode_typed() {
+        let encoded = encode(&("cat", 1_u8, [0xff_u8; 4]));
+        let (name, number, word) = decode::<(String, u8, [u8; 4])>(&encoded).unwrap();
+        assert_eq!((name.as_str(), number, word), ("cat", 1, [0xff; 4]));
+
+        assert_eq!(decode::<(String, u8)>(&encoded), Err(Error::TrailingBytes));
+        assert_eq!(decode::<u8>(&encoded), Err(Error::UnexpectedList));
+        assert_eq!(decode::<(u8,)>(&[0x01]), Err(Error::UnexpectedString));
+
+        let encoded = encode_list(&[1_u32, 2, 3]);
+        assert_eq!(Decoder::new(&encoded).decode_list::<u32>(), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_trailing_bytes() {
+        assert_eq!(decode::<Item>(&[0x01, 0x02]), Err(Error::TrailingBytes));
+        assert_eq!(decode::<Item>(&[0xc2, 0x83, 0x01]), Err(Error::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_stream() {
+        let encoded = [encode("dog"), encode_list(&["cat", "god"]), encode(&1024_u32)].concat();
+
+        let mut stream = Stream::new();
+        let mut items = Vec::new();
+        for chunk in encoded.chunks(3) {
+            stream.extend(chunk);
+            while let Some(item) = stream.next_item().unwrap() {
+                items.push(item);
+            }
+        }
+
+        assert_eq!(items, vec![
+            Item::Bytes(b"dog".to_vec()),
+            Item::List(vec![Item::Bytes(b"cat".to_vec()), Item::Bytes(b"god".to_vec())]),
+            Item::Bytes(vec![0x04, 0x00]),
+        ]);
+        assert_eq!(stream.buffered(), 0);
+
+        stream.extend(&[0x81, 0x01]);
+        assert_eq!(stream.next_item(), Err(Error::NonCanonicalSingleByte));
+    }
+}
@@ -0,0 +1,160 @@
+use num_bigint::BigUint;
+
+pub trait Encodable {
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+pub fn encode<T: Encodable + ?Sized>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.encode(&mut out);
+    out
+}
+
+pub fn encode_list<T: Encodable>(items: &[T]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for item in items {
+        item.encode(&mut payload);
+    }
+
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    encode_header(true, payload.len(), &mut out);
+    out.extend(payload);
+    out
+}
+
+pub fn encode_header(list: bool, length: usize, out: &mut Vec<u8>) {
+    let base = if list { 0xc0 } else { 0x80 };
+    if length < 56 {
+        out.push(base + length as u8);
+    } else {
+        let bytes = length.to_be_bytes();
+        let bytes = &bytes[length.leading_zeros() as usize / 8..];
+        out.push(base + 55 + bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+}
+
+impl<T: Encodable + ?Sized> Encodable for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out)
+    }
+}
+
+impl Encodable for [u8] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            [byte] if *byte < 0x80 => out.push(*byte),
+            _ => {
+                encode_header(false, self.len(), out);
+                out.extend_from_slice(self);
+            }
+        }
+    }
+}
+
+impl Encodable for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl<const N: usize> Encodable for [u8; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out)
+    }
+}
+
+impl Encodable for str {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode(out)
+    }
+}
+
+impl Encodable for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode(out)
+    }
+}
+
+impl Encodable for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u8).encode(out)
+    }
+}
+
+impl Encodable for BigUint {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self.bits() {
+            0 => out.push(0x80),
+            _ => self.to_bytes_be().encode(out),
+        }
+    }
+}
+
+macro_rules! impl_uint {
+    ($($ty:ty),*) => {$(
+        impl Encodable for $ty {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let bytes = self.to_be_bytes();
+                bytes[self.leading_zeros() as usize / 8..].encode(out)
+            }
+        }
+    )*};
+}
+
+impl_uint!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: Encodable),+> Encodable for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, out: &mut Vec<u8>) {
+                let ($($name,)+) = self;
+                let mut payload = Vec::new();
+                $($name.encode(&mut payload);)+
+                encode_header(true, payload.len(), out);
+                out.extend(payload);
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A B);
+impl_tuple!(A B C);
+impl_tuple!(A B C D);
+impl_tuple!(A B C D E);
+impl_tuple!(A B C D E F);
+impl_tuple!(A B C D E F G);
+impl_tuple!(A B C D E F G H);
+impl_tuple!(A B C D E F G H I);
+impl_tuple!(A B C D E F G H I J);
+impl_tuple!(A B C D E F G H I J K);
+impl_tuple!(A B C D E F G H I J K L);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_integers() {
+        assert_eq!(encode(&0_u64), vec![0x80]);
+        assert_eq!(encode(&0x7f_u8), vec![0x7f]);
+        assert_eq!(encode(&0x80_u8), vec![0x81, 0x80]);
+        assert_eq!(encode(&0x0400_u16), vec![0x82, 0x04, 0x00]);
+        assert_eq!(encode(&BigUint::from(0_u8)), vec![0x80]);
+        assert_eq!(encode(&BigUint::from(1024_u32)), vec![0x82, 0x04, 0x00]);
+        assert_eq!(encode(&true), vec![0x01]);
+        assert_eq!(encode(&false), vec![0x80]);
+    }
+
+    #[test]
+    fn test_encode_list() {
+        assert_eq!(encode_list(&["cat", "dog"]), hex::decode("c88363617483646f67").unwrap());
+        assert_eq!(encode(&("cat", 1_u8, ("dog",))), hex::decode("ca8363617401c483646f67").unwrap());
+
+        let long = encode_list(&[[0_u8; 32]; 2]);
+        assert_eq!(long[..2], [0xf8, 66]);
+        assert_eq!(long.len(), 68);
+    }
+}
@@ -0,0 +1,32 @@
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("Unexpected end of input")]
+    UnexpectedEof,
+
+    #[error("Single byte below 0x80 is not encoded as itself")]
+    NonCanonicalSingleByte,
+
+    #[error("Length prefix is not minimal")]
+    NonCanonicalSize,
+
+    #[error("Integer has leading zero bytes")]
+    LeadingZero,
+
+    #[error("Value does not fit in the target type")]
+    Overflow,
+
+    #[error("Expected a byte string, found a list")]
+    UnexpectedList,
+
+    #[error("Expected a list, found a byte string")]
+    UnexpectedString,
+
+    #[error("Lists are nested too deeply")]
+    TooDeep,
+
+    #[error("Trailing bytes after item")]
+    TrailingBytes,
+
+    #[error("Input data is invalid")]
+    InvalidData,
+}
@@ -0,0 +1,57 @@
+// Runs the Ethereum RLP test vectors (ethereum/tests RLPTests format) stored under `fixtures/`.
+use num_bigint::BigUint;
+use serde_json::Value as Json;
+use crate::{decode, encode, Item};
+
+fn load(name: &str) -> serde_json::Map<String, Json> {
+    let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let json = std::fs::read_to_string(path).unwrap();
+    match serde_json::from_str(&json).unwrap() {
+        Json::Object(tests) => tests,
+        _ => panic!("fixture must be an object"),
+    }
+}
+
+fn to_item(json: &Json) -> Item {
+    match json {
+        Json::String(string) => match string.strip_prefix('#') {
+            Some(number) => uint_item(BigUint::parse_bytes(number.as_bytes(), 10).unwrap()),
+            None => Item::Bytes(string.as_bytes().to_vec()),
+        },
+        Json::Number(number) => uint_item(number.as_u64().unwrap().into()),
+        Json::Array(items) => Item::List(items.iter().map(to_item).collect()),
+        _ => panic!("unsupported fixture input: {}", json),
+    }
+}
+
+// Integers are big endian byte strings without leading zeros; zero is the empty string.
+fn uint_item(number: BigUint) -> Item {
+    match number.bits() {
+        0 => Item::Bytes(Vec::new()),
+        _ => Item::Bytes(number.to_bytes_be()),
+    }
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    hex::decode(hex.trim_start_matches("0x")).unwrap()
+}
+
+#[test]
+fn test_valid_vectors() {
+    for (name, test) in load("rlptest.json") {
+        let expected = from_hex(test["out"].as_str().unwrap());
+        let item = to_item(&test["in"]);
+
+        assert_eq!(encode(&item), expected, "{}: encoding mismatch", name);
+        assert_eq!(decode::<Item>(&expected), Ok(item), "{}: decoding mismatch", name);
+    }
+}
+
+#[test]
+fn test_invalid_vectors() {
+    for (name, test) in load("invalidRLPTest.json") {
+        assert_eq!(test["in"], "INVALID");
+        let bytes = from_hex(test["out"].as_str().unwrap());
+        assert!(decode::<Item>(&bytes).is_err(), "{}: decoded invalid input", name);
+    }
+}
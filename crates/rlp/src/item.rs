@@ -0,0 +1,105 @@
+use crate::{Decodable, Decoder, Encodable, Error};
+use crate::encode::encode_header;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Bytes(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    pub fn as_bytes(&self) -> Result<&[u8], Error> {
+        match self {
+            Item::Bytes(bytes) => Ok(bytes),
+            Item::List(_) => Err(Error::UnexpectedList),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Item], Error> {
+        match self {
+            Item::List(items) => Ok(items),
+            Item::Bytes(_) => Err(Error::UnexpectedString),
+        }
+    }
+}
+
+impl Encodable for Item {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Item::Bytes(bytes) => bytes.as_slice().encode(out),
+            Item::List(items) => {
+                let mut payload = Vec::new();
+                for item in items {
+                    item.encode(&mut payload);
+                }
+                encode_header(true, payload.len(), out);
+                out.extend(payload);
+            }
+        }
+    }
+}
+
+// Deeper lists are rejected, so hostile input cannot exhaust the stack.
+const MAX_DEPTH: usize = 1024;
+
+impl Decodable for Item {
+    fn decode(decoder: &mut Decoder<'_>) -> Result<Self, Error> {
+        decode_item(decoder, 0)
+    }
+}
+
+fn decode_item(decoder: &mut Decoder<'_>, depth: usize) -> Result<Item, Error> {
+    if !decoder.header()?.list {
+        return Ok(Item::Bytes(decoder.bytes()?.to_vec()));
+    }
+    if depth == MAX_DEPTH {
+        return Err(Error::TooDeep);
+    }
+
+    let mut list = decoder.list()?;
+    let mut items = Vec::new();
+    while !list.is_empty() {
+        items.push(decode_item(&mut list, depth + 1)?);
+    }
+    Ok(Item::List(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, encode_header};
+
+    // An empty list inside `depth` more lists, built without recursing: 0xc1 prefixes on
+    // the inside, growing into long headers further out.
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut headers = Vec::new();
+        let mut length = 1;
+        for _ in 0..depth {
+            let mut header = Vec::new();
+            encode_header(true, length, &mut header);
+            length += header.len();
+            headers.push(header);
+        }
+
+        let mut bytes: Vec<u8> = headers.into_iter().rev().flatten().collect();
+        bytes.push(0xc0);
+        bytes
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut item = Item::List(Vec::new());
+        for _ in 1..MAX_DEPTH {
+            item = Item::List(vec![item]);
+        }
+        assert_eq!(nested(MAX_DEPTH - 1), encode(&item));
+        assert_eq!(decode::<Item>(&nested(MAX_DEPTH - 1)), Ok(item));
+        assert_eq!(decode::<Item>(&nested(MAX_DEPTH)), Err(Error::TooDeep));
+    }
+
+    #[test]
+    fn test_deeply_nested_prefixes() {
+        assert_eq!(&nested(3), &[0xc3, 0xc2, 0xc1, 0xc0]);
+        assert_eq!(decode::<Item>(&nested(1_000_000)), Err(Error::TooDeep));
+    }
+}
@@ -0,0 +1,21 @@
+pub extern crate num_bigint;
+
+#[cfg(test)]
+extern crate hex;
+#[cfg(test)]
+extern crate serde_json;
+#[macro_use]
+extern crate thiserror;
+
+pub use decode::{decode, Decodable, Decoder, Header, Stream};
+pub use encode::{encode, encode_header, encode_list, Encodable};
+pub use error::Error;
+pub use item::Item;
+
+mod decode;
+mod encode;
+mod error;
+mod item;
+
+#[cfg(test)]
+mod fixtures;
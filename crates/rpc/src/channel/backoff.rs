@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+// Exponential backoff: `initial * multiplier^attempt`, capped at `max`. Jitter shaves a random
+// fraction (up to `jitter`) off every delay so that many clients don't retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_attempts: Option<usize>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    pub fn max_attempts(&self) -> Option<usize> {
+        self.max_attempts
+    }
+
+    // Whether `attempt` (counting from zero) is beyond the configured number of attempts.
+    pub fn exhausted(&self, attempt: usize) -> bool {
+        self.max_attempts.is_some_and(|max_attempts| attempt >= max_attempts)
+    }
+
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+        let scaled = self.initial.as_secs_f64() * self.multiplier.powi(exponent);
+        let delay = Duration::from_secs_f64(scaled.min(self.max.as_secs_f64()));
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 - self.jitter * rand::random::<f64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(0.0);
+
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.delay(usize::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff_jitter() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1))
+            .with_jitter(0.5);
+
+        for attempt in 0..8 {
+            let delay = backoff.delay(attempt);
+            let ceiling = Duration::from_millis(100 << attempt).min(Duration::from_secs(1));
+            assert!(delay <= ceiling);
+            assert!(delay >= ceiling / 2);
+        }
+    }
+
+    #[test]
+    fn test_backoff_attempts() {
+        let backoff = Backoff::default().with_max_attempts(2);
+        assert!(!backoff.exhausted(0));
+        assert!(!backoff.exhausted(1));
+        assert!(backoff.exhausted(2));
+        assert!(!Backoff::default().exhausted(usize::MAX));
+    }
+}
@@ -0,0 +1,1091 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use parking_lot::{Mutex, MutexGuard};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::{WebSocketStream, MaybeTlsStream};
+
+use crate::jsonrpc::{self, Id, JsonRpc, Response};
+use crate::channel::{ConnectionEvent, InFlight, OneshotChannel, Reconnect, Subscriber, SubscriptionChannel};
+use crate::channel::subscription::{self, subscription_key};
+use crate::Error;
+
+type Session = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type Reader = futures::stream::SplitStream<Session>;
+type Outgoing = mpsc::UnboundedSender<tungstenite::Message>;
+type Notifications = mpsc::UnboundedSender<Result<JsonRpc, Error>>;
+type Reply = oneshot::Receiver<Result<Response, Error>>;
+
+pub type ConnectionEvents = mpsc::UnboundedReceiver<ConnectionEvent>;
+
+enum Kind {
+    Call(oneshot::Sender<Result<Response, Error>>),
+    // Registered as a subscription by the reader before any notification is routed.
+    Subscribe(oneshot::Sender<Result<Response, Error>>, Subscription),
+    // Re-establishes the subscription with the given id after a reconnect.
+    Resubscribe(String, Subscription),
+}
+
+// A request waiting for its response. Ids on the wire are assigned by the client, so callers
+// sharing a connection never collide; `id` is the caller's own id, restored on the response.
+struct Pending {
+    id: Id,
+    request: JsonRpc,
+    kind: Kind,
+    // The wire id of the first request in the frame it was sent in.
+    frame: u64,
+    // Times it was sent again after a reconnect.
+    retries: usize,
+}
+
+// Server-side ids change when a subscription is re-established, so the subscriber refers to it
+// by a handle that stays fixed for its lifetime.
+struct Subscription {
+    handle: u64,
+    request: JsonRpc,
+    notifications: Notifications,
+    // Cancelled while being re-established; unsubscribed as soon as the new id is known.
+    cancelled: bool,
+}
+
+// Pings the server every `interval` and drops the connection once it has been silent, pongs
+// included, for `timeout`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Heartbeat {
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+}
+
+enum Ending {
+    // The server closed the connection, with a status if it sent one.
+    Closed(Option<CloseFrame<'static>>),
+    // The server stayed silent for longer than the heartbeat timeout.
+    Silent(Duration),
+    // The connection broke or could not be re-established.
+    Failed,
+    Dropped,
+}
+
+impl Ending {
+    // What the connection ended with, if anything more than it breaking.
+    fn error(&self) -> Option<Error> {
+        match self {
+            Ending::Closed(frame) => Some(Error::closed(frame.clone())),
+            Ending::Silent(silence) => Some(Error::HeartbeatTimeout(*silence)),
+            Ending::Failed | Ending::Dropped => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    // None while disconnected.
+    outgoing: Option<Outgoing>,
+    pending: HashMap<u64, Pending>,
+    subscriptions: HashMap<String, Subscription>,
+    closed: bool,
+}
+
+struct Shared {
+    endpoint: String,
+    reconnect: Option<Reconnect>,
+    heartbeat: Option<Heartbeat>,
+    sequence: AtomicU64,
+    state: Mutex<State>,
+    events: Mutex<Vec<mpsc::UnboundedSender<ConnectionEvent>>>,
+}
+
+impl Shared {
+    fn retries(&self) -> bool {
+        matches!(self.reconnect, Some(Reconnect { in_flight: InFlight::Retry, .. }))
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        self.events.lock().retain(|events| events.unbounded_send(event.clone()).is_ok());
+    }
+
+    fn wire(&self, jsonrpc: &JsonRpc) -> (u64, JsonRpc) {
+        let wire_id = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let mut request = jsonrpc.clone();
+        request.id = Some(Id::Num(wire_id));
+        (wire_id, request)
+    }
+
+    // Registers the requests and sends them in one frame, returning their wire ids. While
+    // reconnecting, requests are queued if in-flight requests are retried and rejected otherwise.
+    fn dispatch(&self, requests: Vec<(&JsonRpc, Kind)>, batch: bool) -> Result<Vec<u64>, Error> {
+        let requests = requests.into_iter()
+            .map(|(jsonrpc, kind)| {
+                let (wire_id, request) = self.wire(jsonrpc);
+                let id = jsonrpc.id.clone().unwrap_or(Id::Num(wire_id));
+                (wire_id, Pending { id, request, kind, frame: wire_id, retries: 0 })
+            })
+            .collect::<Vec<_>>();
+        let frame = requests[0].0;
+        let wire_ids = requests.iter().map(|(wire_id, _)| *wire_id).collect();
+
+        let text = match batch {
+            true => serde_json::to_string(&requests.iter().map(|(_, pending)| &pending.request).collect::<Vec<_>>())?,
+            false => serde_json::to_string(&requests[0].1.request)?,
+        };
+
+        let mut state = self.state.lock();
+        if state.closed {
+            return Err(Error::ConnectionError(self.endpoint.clone()));
+        }
+
+        let sent = state.outgoing.as_ref()
+            .is_some_and(|outgoing| outgoing.unbounded_send(tungstenite::Message::Text(text)).is_ok());
+        if !sent && !self.retries() {
+            return Err(Error::ConnectionError(self.endpoint.clone()));
+        }
+
+        state.pending.extend(requests.into_iter().map(|(wire_id, pending)| (wire_id, Pending { frame, ..pending })));
+        Ok(wire_ids)
+    }
+
+    fn disconnect(&self, ending: &Ending) {
+        let retries = self.retries();
+        let max_retries = self.reconnect.as_ref().map_or(0, |reconnect| reconnect.retries);
+        let error = ending.error().unwrap_or_else(|| Error::ConnectionError(self.endpoint.clone()));
+        let mut state = self.state.lock();
+        state.outgoing = None;
+
+        for (wire_id, pending) in std::mem::take(&mut state.pending) {
+            match pending.kind {
+                // Interrupted resubscriptions are retried on the next reconnect.
+                Kind::Resubscribe(previous, subscription) => {
+                    state.subscriptions.insert(previous, subscription);
+                }
+                kind if retries && pending.retries < max_retries => {
+                    state.pending.insert(wire_id, Pending { kind, retries: pending.retries + 1, ..pending });
+                }
+                Kind::Call(reply) | Kind::Subscribe(reply, _) if retries => {
+                    reply.send(Err(error.duplicate())).ok();
+                }
+                // Dropping the reply fails the caller with a connection error.
+                _ => {}
+            }
+        }
+    }
+
+    // Resends retried requests and re-establishes subscriptions on a fresh connection.
+    fn resume(&self, outgoing: Outgoing) -> bool {
+        let mut state = self.state.lock();
+        if state.closed {
+            return false;
+        }
+
+        // Each is resent in a frame of its own.
+        let mut wire_ids = state.pending.keys().copied().collect::<Vec<_>>();
+        wire_ids.sort_unstable();
+        for wire_id in wire_ids {
+            let pending = state.pending.get_mut(&wire_id).expect("pending request");
+            pending.frame = wire_id;
+            send(&outgoing, &pending.request);
+        }
+
+        for (previous, subscription) in std::mem::take(&mut state.subscriptions) {
+            let (wire_id, request) = self.wire(&subscription.request);
+            send(&outgoing, &request);
+
+            let id = Id::Num(wire_id);
+            let kind = Kind::Resubscribe(previous, subscription);
+            state.pending.insert(wire_id, Pending { id, request, kind, frame: wire_id, retries: 0 });
+        }
+
+        state.outgoing = Some(outgoing);
+        true
+    }
+
+    // Dropping every sender fails waiting callers and ends every subscriber stream. Subscribers
+    // learn why when the server closed the connection or went silent.
+    fn close(&self, ending: Ending) {
+        let mut state = self.state.lock();
+        if state.closed {
+            return;
+        }
+        state.closed = true;
+        if let Some(error) = ending.error() {
+            for subscription in state.subscriptions.values() {
+                subscription.notifications.unbounded_send(Err(error.duplicate())).ok();
+            }
+        }
+        state.outgoing = None;
+        state.pending.clear();
+        state.subscriptions.clear();
+        drop(state);
+
+        // Nothing follows, so event streams end here.
+        self.emit(ConnectionEvent::Closed);
+        self.events.lock().clear();
+    }
+
+    async fn unsubscribe(&self, handle: u64) -> Result<bool, Error> {
+        let (id, subscription) = {
+            let mut state = self.state.lock();
+            let id = state.subscriptions.iter()
+                .find(|(_, subscription)| subscription.handle == handle)
+                .map(|(id, _)| id.clone());
+
+            match id.and_then(|id| state.subscriptions.remove_entry(&id)) {
+                Some(entry) => entry,
+                None => {
+                    for pending in state.pending.values_mut() {
+                        if let Kind::Resubscribe(_, subscription) = &mut pending.kind {
+                            subscription.cancelled |= subscription.handle == handle;
+                        }
+                    }
+                    return Ok(false);
+                }
+            }
+        };
+
+        let caller = subscription.request.id.clone().unwrap_or(Id::Num(handle));
+        let unsubscribe = subscription::unsubscribe_request(&subscription.request, caller, &id);
+        let (reply, receiver) = oneshot::channel();
+        self.dispatch(vec![(&unsubscribe, Kind::Call(reply))], false)?;
+
+        let response = receiver.await.map_err(|_| Error::ConnectionError(self.endpoint.clone()))??;
+        if let Some(error) = response.error {
+            return Err(Error::JsonRpcError(error));
+        }
+        Ok(response.result.and_then(|result| result.as_bool()).unwrap_or(false))
+    }
+
+    fn route(&self, value: serde_json::Value) {
+        let subscription = value.get("params")
+            .and_then(|params| params.get("subscription"))
+            .map(subscription_key);
+
+        if let Some(subscription) = subscription {
+            let notification = match serde_json::from_value::<JsonRpc>(value) {
+                Ok(notification) => notification,
+                Err(err) => return log::error!("Failed to parse notification: {}", err),
+            };
+
+            let state = self.state.lock();
+            let delivered = state.subscriptions.get(&subscription)
+                .map(|subscription| subscription.notifications.unbounded_send(Ok(notification)).is_ok());
+            // A subscriber that went away is unsubscribed by its watcher.
+            if delivered.is_none() {
+                log::debug!("Notification for unknown subscription {}", subscription);
+            }
+            return;
+        }
+
+        if value.get("id").is_none_or(|id| id.is_null()) {
+            return self.fail_frame(value);
+        }
+
+        let response = match serde_json::from_value::<Response>(value) {
+            Ok(response) => response,
+            Err(err) => return log::error!("Failed to parse response: {}", err),
+        };
+
+        let wire_id = match response.id {
+            Id::Num(wire_id) => wire_id,
+            Id::Str(_) => return log::error!("Response with unknown id {:?}", response.id),
+        };
+
+        let mut state = self.state.lock();
+        match state.pending.remove(&wire_id) {
+            Some(pending) => self.settle(state, pending, Ok(response)),
+            None => log::debug!("Response for unknown request {}", wire_id),
+        }
+    }
+
+    // A server answers a frame it could not take at all, such as a batch over its size limit,
+    // with a single error whose id is null. Nothing says which frame that was, so it fails the
+    // oldest one still waiting; the per-request timeout catches any it gets wrong.
+    fn fail_frame(&self, value: serde_json::Value) {
+        let error = match value.get("error") {
+            Some(error) => error.clone(),
+            None => return log::error!("Response without id: {}", value),
+        };
+
+        let state = self.state.lock();
+        let frame = match state.pending.values().map(|pending| pending.frame).min() {
+            Some(frame) => frame,
+            None => return log::debug!("Error for no pending request: {}", error),
+        };
+        let mut wire_ids = state.pending.iter()
+            .filter(|(_, pending)| pending.frame == frame)
+            .map(|(wire_id, _)| *wire_id)
+            .collect::<Vec<_>>();
+        wire_ids.sort_unstable();
+        drop(state);
+
+        for wire_id in wire_ids {
+            let mut state = self.state.lock();
+            if let Some(pending) = state.pending.remove(&wire_id) {
+                self.settle(state, pending, Err(error.clone()));
+            }
+        }
+    }
+
+    // Hands a request its response, or the error that failed its whole frame.
+    fn settle(&self, mut state: MutexGuard<'_, State>, pending: Pending, outcome: Result<Response, serde_json::Value>) {
+        let outcome = outcome.map(|response| Response { id: pending.id.clone(), ..response });
+
+        match pending.kind {
+            Kind::Call(reply) => {
+                reply.send(outcome.map_err(Error::JsonRpcError)).ok();
+            }
+            Kind::Subscribe(reply, subscription) => {
+                let result = outcome.as_ref().ok().and_then(|response| response.result.as_ref());
+                if let Some(result) = result.filter(|result| !result.is_null()) {
+                    state.subscriptions.insert(subscription_key(result), subscription);
+                }
+                reply.send(outcome.map_err(Error::JsonRpcError)).ok();
+            }
+            Kind::Resubscribe(previous, subscription) => {
+                let response = outcome.unwrap_or_else(|error| Response { id: pending.id, result: None, error: Some(error) });
+                let event = match (response.result.as_ref(), response.error) {
+                    (Some(result), None) if subscription.cancelled => {
+                        drop(state);
+                        let unsubscribe = subscription::unsubscribe_request(&subscription.request, Id::Num(0), &subscription_key(result));
+                        let (reply, _) = oneshot::channel();
+                        self.dispatch(vec![(&unsubscribe, Kind::Call(reply))], false).ok();
+                        return;
+                    }
+                    (Some(result), None) => {
+                        let current = subscription_key(result);
+                        state.subscriptions.insert(current.clone(), subscription);
+                        ConnectionEvent::Resubscribed { previous, current }
+                    }
+                    (_, error) => {
+                        let reason = error.as_ref().map_or("empty result".to_string(), |error| error.to_string());
+                        if let Some(error) = error {
+                            subscription.notifications.unbounded_send(Err(Error::JsonRpcError(error))).ok();
+                        }
+                        ConnectionEvent::ResubscribeFailed { subscription: previous, reason }
+                    }
+                };
+                drop(state);
+                self.emit(event);
+            }
+        }
+    }
+}
+
+// One websocket connection shared by any number of concurrent requests and subscriptions.
+// A background reader routes responses by id and notifications by subscription id.
+pub struct WebsocketClient {
+    shared: Arc<Shared>,
+    timeout: Duration,
+}
+
+impl WebsocketClient {
+    pub async fn connect<E>(endpoint: E) -> Result<Self, Error>
+    where
+        E: Into<String>,
+    {
+        Self::establish(endpoint.into(), None, None).await
+    }
+
+    // Reconnects with backoff when the connection drops and re-establishes active subscriptions.
+    pub async fn connect_with_reconnect<E>(endpoint: E, reconnect: Reconnect) -> Result<Self, Error>
+    where
+        E: Into<String>,
+    {
+        Self::establish(endpoint.into(), Some(reconnect), None).await
+    }
+
+    pub(crate) async fn establish(endpoint: String, reconnect: Option<Reconnect>, heartbeat: Option<Heartbeat>) -> Result<Self, Error> {
+        log::info!("Connecting to {}", endpoint.as_str());
+        let (session, _) = tokio_tungstenite::connect_async(endpoint.as_str()).await?;
+        let (writer, reader) = session.split();
+        let (outgoing, receiver) = mpsc::unbounded();
+
+        let shared = Arc::new(Shared {
+            endpoint,
+            reconnect,
+            heartbeat,
+            sequence: AtomicU64::new(1),
+            state: Mutex::new(State { outgoing: Some(outgoing), ..State::default() }),
+            events: Mutex::new(Vec::new()),
+        });
+
+        tokio::spawn(write_loop(writer, receiver));
+        tokio::spawn(supervise(shared.clone(), reader));
+
+        Ok(Self { shared, timeout: Duration::from_secs(60) })
+    }
+
+    // How long a request waits for its response before failing with a deadline error, so one
+    // the server never answers is given up on. Defaults to a minute.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.shared.endpoint
+    }
+
+    // Closed clients stay closed; a new one has to be connected.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.shared.state.lock().closed
+    }
+
+    // Every call returns an independent stream of connection events.
+    pub fn events(&self) -> ConnectionEvents {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut events = self.shared.events.lock();
+        if !self.shared.state.lock().closed {
+            events.push(sender);
+        }
+        receiver
+    }
+
+    async fn receive(&self, wire_id: u64, receiver: Reply, deadline: Instant) -> Result<Response, Error> {
+        match tokio::time::timeout_at(deadline, receiver).await {
+            Ok(reply) => reply.map_err(|_| Error::ConnectionError(self.shared.endpoint.clone()))?,
+            Err(_) => {
+                self.shared.state.lock().pending.remove(&wire_id);
+                Err(Error::DeadlineExceeded(self.timeout))
+            }
+        }
+    }
+}
+
+impl Drop for WebsocketClient {
+    fn drop(&mut self) {
+        self.shared.close(Ending::Dropped);
+    }
+}
+
+#[async_trait]
+impl OneshotChannel for WebsocketClient {
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        let deadline = Instant::now() + self.timeout;
+        let (reply, receiver) = oneshot::channel();
+        let wire_ids = self.shared.dispatch(vec![(jsonrpc, Kind::Call(reply))], false)?;
+        self.receive(wire_ids[0], receiver, deadline).await
+    }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        jsonrpc::validate_batch(batch)?;
+
+        let deadline = Instant::now() + self.timeout;
+        let (requests, receivers): (Vec<_>, Vec<_>) = batch.iter()
+            .map(|jsonrpc| {
+                let (reply, receiver) = oneshot::channel();
+                ((jsonrpc, Kind::Call(reply)), receiver)
+            })
+            .unzip();
+        let wire_ids = self.shared.dispatch(requests, true)?;
+
+        let mut results = Vec::with_capacity(batch.len());
+        for (wire_id, receiver) in wire_ids.into_iter().zip(receivers) {
+            results.push(self.receive(wire_id, receiver, deadline).await);
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl SubscriptionChannel for WebsocketClient {
+    type Item = JsonRpc;
+
+    async fn subscribe(&self, jsonrpc: &JsonRpc) -> Result<Subscriber<Self::Item>, Error> {
+        let (notifications, stream) = mpsc::unbounded();
+        let handle = self.shared.sequence.fetch_add(1, Ordering::Relaxed);
+        let subscription = Subscription { handle, request: jsonrpc.clone(), notifications, cancelled: false };
+
+        let deadline = Instant::now() + self.timeout;
+        let (reply, receiver) = oneshot::channel();
+        let wire_ids = self.shared.dispatch(vec![(jsonrpc, Kind::Subscribe(reply, subscription))], false)?;
+
+        let response = self.receive(wire_ids[0], receiver, deadline).await?;
+        if let Some(error) = response.error {
+            return Err(Error::JsonRpcError(error));
+        }
+        let id = match response.result.as_ref() {
+            Some(result) if !result.is_null() => subscription_key(result),
+            _ => return Err(Error::MissingSubscriptionId),
+        };
+
+        let (cancel, cancelled) = oneshot::channel();
+        tokio::spawn(watch(Arc::downgrade(&self.shared), handle, cancelled));
+        Ok(Subscriber::new(Box::new(stream)).with_cancel(id, cancel))
+    }
+}
+
+// Unsubscribes once the subscriber is dropped or cancelled.
+async fn watch(shared: Weak<Shared>, handle: u64, cancelled: oneshot::Receiver<oneshot::Sender<Result<bool, Error>>>) {
+    let reply = cancelled.await.ok();
+    let result = match shared.upgrade() {
+        Some(shared) => shared.unsubscribe(handle).await,
+        None => Ok(false),
+    };
+
+    match reply {
+        Some(reply) => { reply.send(result).ok(); }
+        None => if let Err(err) = result {
+            log::warn!("Failed to unsubscribe: {}", err);
+        }
+    }
+}
+
+fn send(outgoing: &Outgoing, request: &JsonRpc) {
+    match serde_json::to_string(request) {
+        Ok(text) => { outgoing.unbounded_send(tungstenite::Message::Text(text)).ok(); }
+        Err(err) => log::error!("Failed to format request: {}", err),
+    }
+}
+
+async fn supervise(shared: Arc<Shared>, mut reader: Reader) {
+    loop {
+        let ending = read_loop(&shared, reader).await;
+
+        let reconnect = match shared.reconnect.as_ref() {
+            Some(reconnect) if !shared.state.lock().closed => reconnect,
+            _ => return shared.close(ending),
+        };
+
+        log::warn!("Lost connection to {}", shared.endpoint);
+        shared.disconnect(&ending);
+        shared.emit(ConnectionEvent::Disconnected);
+
+        reader = match reestablish(&shared, reconnect).await {
+            Some(reader) => reader,
+            None => return shared.close(Ending::Failed),
+        };
+    }
+}
+
+async fn reestablish(shared: &Shared, reconnect: &Reconnect) -> Option<Reader> {
+    let mut attempt = 0;
+    while !reconnect.backoff.exhausted(attempt) {
+        let delay = reconnect.backoff.delay(attempt);
+        attempt += 1;
+        shared.emit(ConnectionEvent::Reconnecting { attempt, delay });
+        tokio::time::sleep(delay).await;
+
+        if shared.state.lock().closed {
+            return None;
+        }
+
+        match tokio_tungstenite::connect_async(shared.endpoint.as_str()).await {
+            Ok((session, _)) => {
+                let (writer, reader) = session.split();
+                let (outgoing, receiver) = mpsc::unbounded();
+                tokio::spawn(write_loop(writer, receiver));
+
+                shared.emit(ConnectionEvent::Reconnected { attempts: attempt });
+                return shared.resume(outgoing).then_some(reader);
+            }
+            Err(err) => log::warn!("Failed to reconnect to {}: {}", shared.endpoint, err),
+        }
+    }
+    None
+}
+
+async fn write_loop(
+    mut writer: futures::stream::SplitSink<Session, tungstenite::Message>,
+    mut receiver: mpsc::UnboundedReceiver<tungstenite::Message>,
+) {
+    while let Some(message) = receiver.next().await {
+        if let Err(err) = writer.send(message).await {
+            log::error!("Failed to write to websocket: {}", err);
+            return;
+        }
+    }
+
+    // The client was dropped or the connection was replaced.
+    writer.close().await.ok();
+}
+
+async fn read_loop(shared: &Shared, mut reader: Reader) -> Ending {
+    let mut heartbeat = shared.heartbeat.map(|heartbeat| {
+        let start = Instant::now() + heartbeat.interval;
+        (heartbeat.timeout, tokio::time::interval_at(start, heartbeat.interval))
+    });
+    let mut last_seen = Instant::now();
+
+    loop {
+        let tick = async {
+            match heartbeat.as_mut() {
+                Some((timeout, ticker)) => {
+                    ticker.tick().await;
+                    *timeout
+                }
+                None => futures::future::pending().await,
+            }
+        };
+
+        let message = tokio::select! {
+            message = reader.next() => message,
+            timeout = tick => {
+                let silence = last_seen.elapsed();
+                if silence >= timeout {
+                    log::warn!("No heartbeat from {} for {:?}", shared.endpoint, silence);
+                    return Ending::Silent(silence);
+                }
+                if let Some(outgoing) = shared.state.lock().outgoing.as_ref() {
+                    outgoing.unbounded_send(tungstenite::Message::Ping(Vec::new())).ok();
+                }
+                continue;
+            }
+        };
+
+        let message = match message {
+            Some(message) => message,
+            None => break,
+        };
+        last_seen = Instant::now();
+
+        let value = match message {
+            Ok(tungstenite::Message::Text(text)) => serde_json::from_str::<serde_json::Value>(&text),
+            Ok(tungstenite::Message::Binary(bytes)) => serde_json::from_slice::<serde_json::Value>(&bytes),
+            Ok(tungstenite::Message::Ping(payload)) => {
+                if let Some(outgoing) = shared.state.lock().outgoing.as_ref() {
+                    outgoing.unbounded_send(tungstenite::Message::Pong(payload)).ok();
+                }
+                continue;
+            }
+            Ok(tungstenite::Message::Close(frame)) => {
+                log::info!("Websocket {} closed: {:?}", shared.endpoint, frame);
+                return Ending::Closed(frame);
+            }
+            Ok(_) => continue,
+            Err(err) => {
+                log::error!("Websocket {} failed: {}", shared.endpoint, err);
+                break;
+            }
+        };
+
+        match value {
+            Ok(serde_json::Value::Array(values)) => values.into_iter().for_each(|value| shared.route(value)),
+            Ok(value) => shared.route(value),
+            Err(err) => log::error!("Failed to parse message: {}", err),
+        }
+    }
+    Ending::Failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use std::time::Duration;
+    use futures::future::join;
+    use crate::channel::Backoff;
+
+    async fn server() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        (listener, endpoint)
+    }
+
+    fn text(value: serde_json::Value) -> tungstenite::Message {
+        tungstenite::Message::Text(value.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            // Waits for both requests, then answers in reverse order with a stray notification first.
+            let mut requests = Vec::new();
+            while requests.len() < 2 {
+                if let Some(Ok(tungstenite::Message::Text(text))) = session.next().await {
+                    requests.push(serde_json::from_str::<JsonRpc>(&text).unwrap());
+                }
+            }
+
+            session.send(text(json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xdead", "result": {} },
+            }))).await.unwrap();
+
+            for request in requests.iter().rev() {
+                session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": request.method }))).await.unwrap();
+            }
+            while session.next().await.is_some() {}
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let first = JsonRpc::format(1_u32, "eth_chainId", json!(null));
+        let second = JsonRpc::format(1_u32, "eth_blockNumber", json!(null));
+
+        let (first, second) = join(client.fire(&first), client.fire(&second)).await;
+        let first = first.unwrap();
+        let second = second.unwrap();
+        assert_eq!(first.id, Id::Num(1));
+        assert_eq!(first.as_result::<String>().unwrap().unwrap(), "eth_chainId");
+        assert_eq!(second.id, Id::Num(1));
+        assert_eq!(second.as_result::<String>().unwrap().unwrap(), "eth_blockNumber");
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejected() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            // Batches are refused as a whole, with an error that has no id to route it by.
+            while let Some(Ok(tungstenite::Message::Text(message))) = session.next().await {
+                let response = match serde_json::from_str::<JsonRpc>(&message) {
+                    Ok(request) => json!({ "jsonrpc": "2.0", "id": request.id, "result": "0x1" }),
+                    Err(_) => json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32600, "message": "batch too large" } }),
+                };
+                session.send(text(response)).await.unwrap();
+            }
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap().with_timeout(Duration::from_secs(5));
+        let batch = vec![
+            JsonRpc::format(1_u32, "eth_chainId", json!(null)),
+            JsonRpc::format(2_u32, "eth_blockNumber", json!(null)),
+        ];
+
+        let results = client.fire_batch(&batch).await.unwrap();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            match result {
+                Err(Error::JsonRpcError(error)) => assert_eq!(error["message"], "batch too large"),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(client.fire(&batch[0]).await.unwrap().id, Id::Num(1));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            // Answers only the second request.
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            session.next().await;
+            let message = session.next().await.unwrap().unwrap().into_text().unwrap();
+            let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+            session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": "0x1" }))).await.unwrap();
+            while session.next().await.is_some() {}
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap().with_timeout(Duration::from_millis(50));
+        let jsonrpc = JsonRpc::format(1_u32, "eth_chainId", json!(null));
+
+        let result = client.fire(&jsonrpc).await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded(timeout)) if timeout == Duration::from_millis(50)));
+        assert!(client.shared.state.lock().pending.is_empty());
+        assert_eq!(client.fire(&jsonrpc).await.unwrap().id, Id::Num(1));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_routing() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            while let Some(Ok(tungstenite::Message::Text(message))) = session.next().await {
+                let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+                match request.method.as_str() {
+                    "eth_subscribe" => {
+                        session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": "0xabc" }))).await.unwrap();
+                        for number in 1..=2 {
+                            session.send(text(json!({
+                                "jsonrpc": "2.0",
+                                "method": "eth_subscription",
+                                "params": { "subscription": "0xabc", "result": { "number": number } },
+                            }))).await.unwrap();
+                        }
+                    }
+                    _ => {
+                        session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": "0x1" }))).await.unwrap();
+                    }
+                }
+            }
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let subscribe = JsonRpc::format(7_u32, "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = client.subscribe(&subscribe).await.unwrap();
+
+        let response = client.fire(&JsonRpc::format(8_u32, "eth_blockNumber", json!(null))).await.unwrap();
+        assert_eq!(response.id, Id::Num(8));
+
+        for number in 1..=2 {
+            let notification = subscriber.next().await.unwrap().unwrap();
+            assert_eq!(notification.method, "eth_subscription");
+            assert_eq!(notification.params["result"]["number"], number);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            session.next().await;
+            session.close(None).await.ok();
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let result = client.fire(&JsonRpc::format(1_u32, "eth_chainId", json!(null))).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+
+        let result = client.fire(&JsonRpc::format(2_u32, "eth_chainId", json!(null))).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+    }
+
+    fn reconnect() -> Reconnect {
+        Reconnect::new(Backoff::new(Duration::from_millis(10), Duration::from_millis(50)).with_jitter(0.0))
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resubscribe() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            // Each connection hands out its own subscription id, then the first one drops.
+            for (connection, subscription) in ["0x1", "0x2"].into_iter().enumerate() {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+                let message = session.next().await.unwrap().unwrap().into_text().unwrap();
+                let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+                assert_eq!(request.method, "eth_subscribe");
+
+                session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": subscription }))).await.unwrap();
+                session.send(text(json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_subscription",
+                    "params": { "subscription": subscription, "result": { "number": connection } },
+                }))).await.unwrap();
+
+                match connection {
+                    0 => session.close(None).await.unwrap(),
+                    _ => while session.next().await.is_some() {},
+                }
+            }
+        });
+
+        let client = WebsocketClient::connect_with_reconnect(endpoint, reconnect()).await.unwrap();
+        let mut events = client.events();
+        let subscribe = JsonRpc::format(7_u32, "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = client.subscribe(&subscribe).await.unwrap();
+
+        for number in 0..2 {
+            let notification = subscriber.next().await.unwrap().unwrap();
+            assert_eq!(notification.params["result"]["number"], number);
+        }
+
+        let expected = vec![
+            ConnectionEvent::Disconnected,
+            ConnectionEvent::Reconnecting { attempt: 1, delay: Duration::from_millis(10) },
+            ConnectionEvent::Reconnected { attempts: 1 },
+            ConnectionEvent::Resubscribed { previous: "0x1".to_string(), current: "0x2".to_string() },
+        ];
+        for event in expected {
+            assert_eq!(events.next().await.unwrap(), event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_in_flight() {
+        for in_flight in [InFlight::Retry, InFlight::Fail] {
+            let (listener, endpoint) = server().await;
+            tokio::spawn(async move {
+                // The first connection drops the request unanswered, later ones answer everything.
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                session.next().await;
+                session.close(None).await.unwrap();
+
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(tungstenite::Message::Text(message))) = session.next().await {
+                    let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+                    session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": "0x1" }))).await.unwrap();
+                }
+            });
+
+            let client = WebsocketClient::connect_with_reconnect(endpoint, reconnect().with_in_flight(in_flight)).await.unwrap();
+            let mut events = client.events();
+            let jsonrpc = JsonRpc::format(3_u32, "eth_blockNumber", json!(null));
+
+            let result = client.fire(&jsonrpc).await;
+            match in_flight {
+                InFlight::Retry => assert_eq!(result.unwrap().id, Id::Num(3)),
+                InFlight::Fail => {
+                    assert!(matches!(result, Err(Error::ConnectionError(_))));
+                    while !matches!(events.next().await, Some(ConnectionEvent::Reconnected { .. })) {}
+                    assert_eq!(client.fire(&jsonrpc).await.unwrap().id, Id::Num(3));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_retries() {
+        let (listener, endpoint) = server().await;
+        let (sent, mut sends) = mpsc::unbounded();
+        tokio::spawn(async move {
+            // Every connection drops the request unanswered, going away with a status.
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                session.next().await;
+                sent.unbounded_send(()).unwrap();
+                session.close(Some(tungstenite::protocol::CloseFrame {
+                    code: tungstenite::protocol::frame::coding::CloseCode::Away,
+                    reason: "going away".into(),
+                })).await.ok();
+            }
+        });
+
+        let reconnect = reconnect().with_in_flight(InFlight::Retry).with_retries(2);
+        let client = WebsocketClient::connect_with_reconnect(endpoint, reconnect).await.unwrap();
+
+        let result = client.fire(&JsonRpc::format(1_u32, "eth_chainId", json!(null))).await;
+        assert!(matches!(result, Err(Error::ConnectionClosed { code: 1001, .. })));
+        for _ in 0..3 {
+            sends.next().await.unwrap();
+        }
+        assert!(client.shared.state.lock().pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_exhausted() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            session.next().await;
+            session.close(None).await.ok();
+            // The listener goes away, so every reconnect attempt is refused.
+        });
+
+        let reconnect = Reconnect::new(Backoff::new(Duration::from_millis(1), Duration::from_millis(1)).with_max_attempts(2));
+        let client = WebsocketClient::connect_with_reconnect(endpoint, reconnect).await.unwrap();
+        let events = client.events();
+
+        let result = client.fire(&JsonRpc::format(1_u32, "eth_chainId", json!(null))).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+
+        let events = events.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 4);
+        assert_eq!(events.first(), Some(&ConnectionEvent::Disconnected));
+        assert_eq!(events.last(), Some(&ConnectionEvent::Closed));
+
+        let result = client.fire(&JsonRpc::format(2_u32, "eth_chainId", json!(null))).await;
+        assert!(matches!(result, Err(Error::ConnectionError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_null_subscription() {
+        let (listener, endpoint) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(tungstenite::Message::Text(message))) = session.next().await {
+                let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+                session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": null }))).await.unwrap();
+            }
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let subscribe = JsonRpc::format(7_u32, "eth_subscribe", json!(["newHeads"]));
+        assert!(matches!(client.subscribe(&subscribe).await, Err(Error::MissingSubscriptionId)));
+        assert!(client.shared.state.lock().subscriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe() {
+        let (listener, endpoint) = server().await;
+        let (unsubscribed, mut unsubscriptions) = mpsc::unbounded();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            let mut subscriptions = ["0xabc", "0xdef"].into_iter();
+            while let Some(Ok(tungstenite::Message::Text(message))) = session.next().await {
+                let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+                let result = match request.method.as_str() {
+                    "eth_subscribe" => json!(subscriptions.next().unwrap()),
+                    "eth_unsubscribe" => {
+                        unsubscribed.unbounded_send(request.params[0].clone()).unwrap();
+                        json!(true)
+                    }
+                    _ => unreachable!(),
+                };
+                session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": result }))).await.unwrap();
+            }
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let subscribe = JsonRpc::format(7_u32, "eth_subscribe", json!(["newHeads"]));
+
+        let subscriber = client.subscribe(&subscribe).await.unwrap();
+        assert_eq!(subscriber.id(), Some("0xabc"));
+        assert!(subscriber.unsubscribe().await.unwrap());
+        assert_eq!(unsubscriptions.next().await.unwrap(), json!("0xabc"));
+
+        let subscriber = client.subscribe(&subscribe).await.unwrap();
+        drop(subscriber);
+        assert_eq!(unsubscriptions.next().await.unwrap(), json!("0xdef"));
+    }
+
+    #[tokio::test]
+    async fn test_close_status() {
+        let (listener, endpoint) = server().await;
+        let (pong, mut pongs) = mpsc::unbounded();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            let message = session.next().await.unwrap().unwrap().into_text().unwrap();
+            let request = serde_json::from_str::<JsonRpc>(&message).unwrap();
+            session.send(tungstenite::Message::Ping(b"ping".to_vec())).await.unwrap();
+            session.send(text(json!({ "jsonrpc": "2.0", "id": request.id, "result": "0xabc" }))).await.unwrap();
+
+            while let Some(Ok(message)) = session.next().await {
+                if let tungstenite::Message::Pong(payload) = message {
+                    pong.unbounded_send(payload).unwrap();
+                    break;
+                }
+            }
+            session.close(Some(tungstenite::protocol::CloseFrame {
+                code: tungstenite::protocol::frame::coding::CloseCode::Away,
+                reason: "going away".into(),
+            })).await.unwrap();
+            while session.next().await.is_some() {}
+        });
+
+        let client = WebsocketClient::connect(endpoint).await.unwrap();
+        let subscribe = JsonRpc::format(7_u32, "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = client.subscribe(&subscribe).await.unwrap();
+
+        assert_eq!(pongs.next().await.unwrap(), b"ping".to_vec());
+        match subscriber.next().await {
+            Some(Err(Error::ConnectionClosed { code, reason })) => {
+                assert_eq!(code, 1001);
+                assert_eq!(reason, "going away");
+            }
+            other => panic!("unexpected {:?}", other.map(|item| item.map(|jsonrpc| jsonrpc.method))),
+        }
+        assert!(subscriber.next().await.is_none());
+    }
+}
@@ -0,0 +1,286 @@
+use std::sync::Arc;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+use crate::jsonrpc::{JsonRpc, Response};
+use crate::channel::OneshotChannel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProviderId(u64);
+
+struct Provider {
+    id: ProviderId,
+    channel: Arc<dyn OneshotChannel<Output=Response>>,
+    weight: u32,
+    max_concurrency: Option<usize>,
+    outstanding: usize,
+    // Smooth weighted round-robin counter, used to break ties between equally loaded providers.
+    current: i64,
+}
+
+impl Provider {
+    fn is_available(&self) -> bool {
+        self.max_concurrency.is_none_or(|max| self.outstanding < max)
+    }
+}
+
+#[derive(Default)]
+struct Pool {
+    providers: Vec<Provider>,
+    sequence: u64,
+}
+
+// Spreads calls over its channels, sending each one to the channel with the fewest outstanding
+// calls for its weight. Calls wait, without blocking the runtime, while every channel is at its
+// concurrency limit or none has been added yet.
+pub struct BalancedChannel {
+    pool: Mutex<Pool>,
+    available: Notify,
+}
+
+impl Default for BalancedChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BalancedChannel {
+    pub fn new() -> Self {
+        Self {
+            pool: Mutex::new(Pool::default()),
+            available: Notify::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pool.lock().providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pool.lock().providers.is_empty()
+    }
+
+    pub fn push_channel<C>(&self, channel: C) -> ProviderId
+    where
+        C: OneshotChannel<Output=Response> + 'static,
+    {
+        self.push_weighted_channel(channel, 1, None)
+    }
+
+    pub fn push_weighted_channel<C>(&self, channel: C, weight: u32, max_concurrency: Option<usize>) -> ProviderId
+    where
+        C: OneshotChannel<Output=Response> + 'static,
+    {
+        let mut pool = self.pool.lock();
+        pool.sequence += 1;
+        let id = ProviderId(pool.sequence);
+        pool.providers.push(Provider {
+            id,
+            channel: Arc::new(channel),
+            weight: weight.max(1),
+            max_concurrency,
+            outstanding: 0,
+            current: 0,
+        });
+        drop(pool);
+
+        self.available.notify_waiters();
+        id
+    }
+
+    // Calls already sent to the channel run to completion.
+    pub fn remove_channel(&self, id: ProviderId) -> bool {
+        let mut pool = self.pool.lock();
+        let before = pool.providers.len();
+        pool.providers.retain(|provider| provider.id != id);
+        pool.providers.len() != before
+    }
+
+    pub fn outstanding(&self, id: ProviderId) -> Option<usize> {
+        let pool = self.pool.lock();
+        pool.providers.iter().find(|provider| provider.id == id).map(|provider| provider.outstanding)
+    }
+
+    fn select(&self) -> Option<(ProviderId, Arc<dyn OneshotChannel<Output=Response>>)> {
+        let mut pool = self.pool.lock();
+        let mut candidates = pool.providers.iter_mut().filter(|provider| provider.is_available()).collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let total = candidates.iter().map(|provider| provider.weight as i64).sum::<i64>();
+        for provider in candidates.iter_mut() {
+            provider.current += provider.weight as i64;
+        }
+
+        // Lowest outstanding per weight first, then the highest round-robin counter.
+        let chosen = candidates.into_iter()
+            .min_by(|a, b| {
+                let load_a = a.outstanding as u64 * b.weight as u64;
+                let load_b = b.outstanding as u64 * a.weight as u64;
+                load_a.cmp(&load_b).then(b.current.cmp(&a.current))
+            })
+            .unwrap();
+
+        chosen.current -= total;
+        chosen.outstanding += 1;
+        Some((chosen.id, chosen.channel.clone()))
+    }
+
+    async fn acquire(&self) -> Lease<'_> {
+        loop {
+            let notified = self.available.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some((id, channel)) = self.select() {
+                return Lease { balancer: self, id, channel };
+            }
+            notified.await;
+        }
+    }
+
+    fn release(&self, id: ProviderId) {
+        let mut pool = self.pool.lock();
+        if let Some(provider) = pool.providers.iter_mut().find(|provider| provider.id == id) {
+            provider.outstanding -= 1;
+        }
+        drop(pool);
+
+        self.available.notify_waiters();
+    }
+}
+
+// Counts a call against its provider until dropped, so cancelled calls are released as well.
+struct Lease<'a> {
+    balancer: &'a BalancedChannel,
+    id: ProviderId,
+    channel: Arc<dyn OneshotChannel<Output=Response>>,
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        self.balancer.release(self.id);
+    }
+}
+
+#[async_trait]
+impl OneshotChannel for BalancedChannel {
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, crate::Error> {
+        let lease = self.acquire().await;
+        lease.channel.fire(jsonrpc).await
+    }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, crate::Error>>, crate::Error> {
+        let lease = self.acquire().await;
+        lease.channel.fire_batch(batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+    use super::*;
+    use crate::channel::HttpChannel;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+    use crate::network::{EthereumNetwork, NetworkOptions};
+
+    async fn answer(channel: &BalancedChannel) -> String {
+        let jsonrpc = JsonRpc::format(Id::Num(1), "eth_chainId", json!(null));
+        channel.fire(&jsonrpc).await.unwrap().as_result::<String>().unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_balanced_weights() {
+        let channel = BalancedChannel::new();
+        channel.push_weighted_channel(MockChannel::named("heavy"), 3, None);
+        channel.push_channel(MockChannel::named("light"));
+
+        let mut answers = Vec::new();
+        for _ in 0..8 {
+            answers.push(answer(&channel).await);
+        }
+        assert_eq!(answers.iter().filter(|name| *name == "heavy").count(), 6);
+        assert_eq!(answers.iter().filter(|name| *name == "light").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_balanced_least_outstanding() {
+        let channel = Arc::new(BalancedChannel::new());
+        let slow = channel.push_channel(MockChannel::named("slow").with_latency(Duration::from_millis(100)));
+        channel.push_channel(MockChannel::named("fast"));
+
+        let pending = tokio::spawn({
+            let channel = channel.clone();
+            async move { answer(&channel).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(channel.outstanding(slow), Some(1));
+
+        // The slow channel is still busy, so everything goes to the fast one.
+        for _ in 0..3 {
+            assert_eq!(answer(&channel).await, "fast");
+        }
+        assert_eq!(pending.await.unwrap(), "slow");
+        assert_eq!(channel.outstanding(slow), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_balanced_max_concurrency() {
+        let channel = Arc::new(BalancedChannel::new());
+        let inner = MockChannel::named("limited").with_latency(Duration::from_millis(20));
+        let peak = inner.peak.clone();
+        channel.push_weighted_channel(inner, 1, Some(2));
+
+        let calls = (0..6).map(|_| {
+            let channel = channel.clone();
+            tokio::spawn(async move { answer(&channel).await })
+        }).collect::<Vec<_>>();
+        for call in calls {
+            assert_eq!(call.await.unwrap(), "limited");
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_balanced_runtime_changes() {
+        let channel = Arc::new(BalancedChannel::new());
+
+        // Calls wait for a channel to be added rather than failing or blocking the runtime.
+        let pending = tokio::spawn({
+            let channel = channel.clone();
+            async move { answer(&channel).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let first = channel.push_channel(MockChannel::named("first"));
+        assert_eq!(pending.await.unwrap(), "first");
+
+        channel.push_channel(MockChannel::named("second"));
+        assert!(channel.remove_channel(first));
+        assert!(!channel.remove_channel(first));
+        assert_eq!(channel.len(), 1);
+        for _ in 0..3 {
+            assert_eq!(answer(&channel).await, "second");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roundrobin_channel() {
+        let roundrobit = BalancedChannel::new();
+        roundrobit.push_channel(HttpChannel::new("https://api.wemix.com"));
+        roundrobit.push_channel(HttpChannel::new("https://public-node-api.klaytnapi.com/v1/cypress"));
+
+        let network = EthereumNetwork::new(NetworkOptions { radix: 16 });
+
+        for _ in 0..2 {
+            let wemix_chain_id = network.chain_id(&roundrobit).await.unwrap();
+            assert_eq!(wemix_chain_id.unwrap(), 1111.into());
+
+            let klaytn_chain_id = network.chain_id(&roundrobit).await.unwrap();
+            assert_eq!(klaytn_chain_id.unwrap(), 8217.into());
+        }
+    }
+}
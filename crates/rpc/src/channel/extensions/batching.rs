@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use futures::future::join_all;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use crate::channel::OneshotChannel;
+use crate::channel::extensions::CacheKey;
+use crate::jsonrpc::{Id, JsonRpc, Response};
+use crate::Error;
+
+type Waiter = (Id, oneshot::Sender<Result<Response, Error>>);
+
+#[derive(Default)]
+struct State {
+    // Callers waiting on each distinct request, from the moment it is queued until it is answered.
+    waiting: HashMap<CacheKey, Vec<Waiter>>,
+    // Requests for the next batch, not sent yet.
+    queued: Vec<(CacheKey, JsonRpc)>,
+}
+
+// Merges identical requests that are in flight at the same time into one upstream call, and sends
+// the distinct requests made within the window together as one batch. Requests without an id are
+// passed straight through.
+pub struct BatchingChannel<C> {
+    inner: Arc<C>,
+    state: Arc<Mutex<State>>,
+    sequence: Arc<AtomicU64>,
+    window: Duration,
+    max_batch: usize,
+    deduplicate: bool,
+}
+
+impl<C> BatchingChannel<C>
+where
+    C: OneshotChannel<Output=Response> + 'static,
+{
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            state: Arc::new(Mutex::new(State::default())),
+            sequence: Arc::new(AtomicU64::new(0)),
+            window: Duration::from_millis(5),
+            max_batch: 100,
+            deduplicate: true,
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    pub fn with_deduplication(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    // Queues the request, starting a flush when it opens a new window.
+    fn enqueue(&self, jsonrpc: &JsonRpc, id: Id) -> oneshot::Receiver<Result<Response, Error>> {
+        let (sender, receiver) = oneshot::channel();
+        let mut key = CacheKey::new(jsonrpc);
+        if !self.deduplicate {
+            // A key of its own, so it never matches another caller.
+            key.method = format!("{}#{}", key.method, self.sequence.fetch_add(1, Ordering::Relaxed));
+        }
+
+        let mut state = self.state.lock();
+        if let Some(waiters) = state.waiting.get_mut(&key) {
+            waiters.push((id, sender));
+            return receiver;
+        }
+
+        state.waiting.insert(key.clone(), vec![(id, sender)]);
+        state.queued.push((key, jsonrpc.clone()));
+        if state.queued.len() == 1 {
+            tokio::spawn(flush(self.inner.clone(), self.state.clone(), self.sequence.clone(), self.window, self.max_batch));
+        }
+        receiver
+    }
+}
+
+async fn flush<C>(inner: Arc<C>, state: Arc<Mutex<State>>, sequence: Arc<AtomicU64>, window: Duration, max_batch: usize)
+where
+    C: OneshotChannel<Output=Response>,
+{
+    tokio::time::sleep(window).await;
+    let queued = std::mem::take(&mut state.lock().queued);
+
+    let chunks = queued.chunks(max_batch).map(|chunk| {
+        let mut pending = Pending { state: &state, chunk, answered: 0 };
+        let inner = &inner;
+        let sequence = &sequence;
+        async move {
+            // Upstream ids are our own, since callers' ids may clash within a batch.
+            let batch = chunk.iter()
+                .map(|(_, jsonrpc)| JsonRpc { id: Some(Id::Num(sequence.fetch_add(1, Ordering::Relaxed))), ..jsonrpc.clone() })
+                .collect::<Vec<_>>();
+
+            let results = match batch.as_slice() {
+                [jsonrpc] => vec![inner.fire(jsonrpc).await],
+                batch => match inner.fire_batch(batch).await {
+                    Ok(results) => results,
+                    Err(err) => batch.iter().map(|_| Err(err.duplicate())).collect(),
+                },
+            };
+
+            // Requests the upstream left unanswered are reported missing, under each caller's id.
+            let mut results = results.into_iter();
+            for (key, _) in chunk {
+                pending.answer(key, results.next().unwrap_or(Err(Error::MissingResponse(Id::Num(0)))));
+            }
+        }
+    });
+    join_all(chunks).await;
+}
+
+// The part of a chunk still to be answered. Should the flush unwind before getting to them, their
+// waiters are cleared when this is dropped, so the callers get an error instead of waiting forever
+// and later requests for the same keys don't join them.
+struct Pending<'a> {
+    state: &'a Mutex<State>,
+    chunk: &'a [(CacheKey, JsonRpc)],
+    answered: usize,
+}
+
+impl Pending<'_> {
+    fn answer(&mut self, key: &CacheKey, result: Result<Response, Error>) {
+        let waiters = self.state.lock().waiting.remove(key).unwrap_or_default();
+        self.answered += 1;
+        answer(waiters, result);
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock();
+        for (key, _) in &self.chunk[self.answered..] {
+            state.waiting.remove(key);
+        }
+    }
+}
+
+// Hands the result to every waiting caller under the caller's own id.
+fn answer(waiters: Vec<Waiter>, result: Result<Response, Error>) {
+    for (id, sender) in waiters {
+        let result = match &result {
+            Ok(response) => Ok(Response { id: id.clone(), ..response.clone() }),
+            Err(Error::MissingResponse(_)) => Err(Error::MissingResponse(id)),
+            Err(err) => Err(err.duplicate()),
+        };
+        let _ = sender.send(result);
+    }
+}
+
+#[async_trait]
+impl<C> OneshotChannel for BatchingChannel<C>
+where
+    C: OneshotChannel<Output=Response> + 'static,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        let id = match &jsonrpc.id {
+            Some(id) => id.clone(),
+            None => return self.inner.fire(jsonrpc).await,
+        };
+
+        self.enqueue(jsonrpc, id).await
+            .unwrap_or(Err(Error::ResponseDroppedError))
+    }
+
+    // The requests join whatever else is being batched rather than going out on their own.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        Ok(join_all(batch.iter().map(|jsonrpc| self.fire(jsonrpc))).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+
+    // Answers with the method called after a short delay.
+    fn recording() -> MockChannel {
+        MockChannel::new()
+            .with_answer(|jsonrpc| json!([jsonrpc.method, jsonrpc.params]))
+            .with_latency(Duration::from_millis(10))
+    }
+
+    async fn fire_all(channel: &BatchingChannel<MockChannel>, requests: Vec<JsonRpc>) -> Vec<Result<Response, Error>> {
+        join_all(requests.iter().map(|jsonrpc| channel.fire(jsonrpc))).await
+    }
+
+    #[tokio::test]
+    async fn test_batching_deduplicates() {
+        let channel = BatchingChannel::new(recording());
+        let requests = (0..100_u64).map(|id| JsonRpc::format(id, "eth_blockNumber", json!(null))).collect();
+
+        let responses = fire_all(&channel, requests).await;
+        for (id, response) in responses.into_iter().enumerate() {
+            let response = response.unwrap();
+            assert_eq!(response.id, Id::Num(id as u64));
+            assert_eq!(response.result, Some(json!(["eth_blockNumber", null])));
+        }
+        assert_eq!(*channel.inner().batches.lock(), vec![1]);
+
+        // Identical requests are only merged while one of them is in flight.
+        channel.fire(&JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null))).await.unwrap();
+        assert_eq!(*channel.inner().batches.lock(), vec![1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_batching_window() {
+        let channel = BatchingChannel::new(recording()).with_max_batch(4);
+        let requests = (0..10_u64)
+            .map(|id| JsonRpc::format(Id::Num(id % 2), "eth_getBalance", json!([format!("0x{:02x}", id), "latest"])))
+            .collect();
+
+        let responses = fire_all(&channel, requests).await;
+        for (index, response) in responses.into_iter().enumerate() {
+            let response = response.unwrap();
+            assert_eq!(response.id, Id::Num(index as u64 % 2));
+            assert_eq!(response.result.unwrap()[1][0], format!("0x{:02x}", index));
+        }
+        assert_eq!(*channel.inner().batches.lock(), vec![4, 4, 2]);
+
+        let channel = BatchingChannel::new(recording()).with_deduplication(false);
+        let requests = (0..3_u64).map(|id| JsonRpc::format(id, "eth_chainId", json!(null))).collect();
+        fire_all(&channel, requests).await;
+        assert_eq!(*channel.inner().batches.lock(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_batching_errors() {
+        let channel = BatchingChannel::new(recording().failing(|| Err(Error::HttpStatus(503))));
+        let requests = vec![
+            JsonRpc::format(1_u64, "eth_chainId", json!(null)),
+            JsonRpc::format(2_u64, "eth_chainId", json!(null)),
+            JsonRpc::format(3_u64, "eth_gasPrice", json!(null)),
+        ];
+
+        let responses = fire_all(&channel, requests).await;
+        assert!(responses.iter().all(|response| matches!(response, Err(Error::HttpStatus(503)))));
+        assert_eq!(*channel.inner().batches.lock(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_batching_missing_responses() {
+        let channel = BatchingChannel::new(recording().with_batch_limit(2));
+        let requests = (0..3_u64).map(|id| JsonRpc::format(id, "eth_getBalance", json!([id]))).collect();
+
+        let responses = fire_all(&channel, requests).await;
+        assert!(responses[0].is_ok() && responses[1].is_ok());
+        assert!(matches!(&responses[2], Err(Error::MissingResponse(Id::Num(2)))));
+        assert!(channel.state.lock().waiting.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batching_upstream_panic() {
+        let panicked = std::sync::atomic::AtomicBool::new(false);
+        let channel = BatchingChannel::new(recording().with_answer(move |_| {
+            assert!(panicked.swap(true, Ordering::Relaxed), "upstream bug");
+            json!("0x1")
+        }));
+        let jsonrpc = JsonRpc::format(1_u64, "eth_chainId", json!(null));
+
+        let fire = || tokio::time::timeout(Duration::from_secs(1), channel.fire(&jsonrpc));
+        assert!(matches!(fire().await.unwrap(), Err(Error::ResponseDroppedError)));
+        // The same request isn't left joining the callers of the panicked flush.
+        assert_eq!(fire().await.unwrap().unwrap().result, Some(json!("0x1")));
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+use crate::channel::OneshotChannel;
+use crate::channel::extensions::{Failure, RetryPolicy, TransientPolicy};
+use crate::jsonrpc::{JsonRpc, Response};
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    // Calls go through and failures are counted.
+    Closed,
+    // Calls fail at once until the cool-down is over.
+    Open,
+    // A few probe calls go through to decide whether to close again.
+    HalfOpen,
+}
+
+type Transition = Box<dyn Fn(BreakerState, BreakerState) + Send + Sync>;
+
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    // Recent outcomes while closed, true for failures.
+    outcomes: VecDeque<bool>,
+    opened_at: Instant,
+    probes: u32,
+    probes_succeeded: u32,
+}
+
+impl Breaker {
+    fn enter(&mut self, state: BreakerState) -> Option<(BreakerState, BreakerState)> {
+        let previous = std::mem::replace(&mut self.state, state);
+        self.consecutive_failures = 0;
+        self.outcomes.clear();
+        self.probes = 0;
+        self.probes_succeeded = 0;
+        if state == BreakerState::Open {
+            self.opened_at = Instant::now();
+        }
+        (previous != state).then_some((previous, state))
+    }
+}
+
+// Stops calling the inner channel once it fails too often, either too many times in a row or at
+// too high a rate over recent calls, and fails fast with `Error::CircuitOpen` instead. After the
+// cool-down, probe calls go through and enough of them succeeding closes the circuit again.
+// Only failures the policy deems transient count, and so do calls running past the call timeout,
+// 30 seconds unless set, so that a hanging channel trips the breaker too.
+pub struct CircuitBreakerChannel<C> {
+    inner: C,
+    breaker: Mutex<Breaker>,
+    failure_threshold: u32,
+    error_rate: Option<(f64, usize)>,
+    cooldown: Duration,
+    probes: u32,
+    call_timeout: Duration,
+    policy: Box<dyn RetryPolicy>,
+    transitions: Vec<Transition>,
+    rejected: AtomicU64,
+}
+
+impl<C> CircuitBreakerChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            breaker: Mutex::new(Breaker {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                outcomes: VecDeque::new(),
+                opened_at: Instant::now(),
+                probes: 0,
+                probes_succeeded: 0,
+            }),
+            failure_threshold: 5,
+            error_rate: None,
+            cooldown: Duration::from_secs(30),
+            probes: 1,
+            call_timeout: Duration::from_secs(30),
+            policy: Box::new(TransientPolicy::default()),
+            transitions: Vec::new(),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_failure_threshold(mut self, consecutive_failures: u32) -> Self {
+        self.failure_threshold = consecutive_failures.max(1);
+        self
+    }
+
+    // Opens once at least `rate` of the last `window` calls failed.
+    pub fn with_error_rate(mut self, rate: f64, window: usize) -> Self {
+        self.error_rate = Some((rate, window.max(1)));
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    // Probes let through at once while half-open, all of which must succeed to close.
+    pub fn with_probes(mut self, probes: u32) -> Self {
+        self.probes = probes.max(1);
+        self
+    }
+
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = timeout;
+        self
+    }
+
+    pub fn with_policy<P>(mut self, policy: P) -> Self
+    where
+        P: RetryPolicy + 'static,
+    {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    // Called with the previous and the new state on every transition.
+    pub fn on_transition<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(BreakerState, BreakerState) + Send + Sync + 'static,
+    {
+        self.transitions.push(Box::new(callback));
+        self
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.breaker.lock().state
+    }
+
+    // How many calls failed fast because the circuit was open.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn notify(&self, transition: Option<(BreakerState, BreakerState)>) {
+        if let Some((previous, current)) = transition {
+            log::warn!("Circuit breaker {:?} -> {:?}", previous, current);
+            for callback in &self.transitions {
+                callback(previous, current);
+            }
+        }
+    }
+
+    fn admit(&self) -> Result<Permit<'_, C>, Error> {
+        let mut breaker = self.breaker.lock();
+        let mut transition = None;
+        if breaker.state == BreakerState::Open {
+            let elapsed = breaker.opened_at.elapsed();
+            if elapsed < self.cooldown {
+                drop(breaker);
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(Error::CircuitOpen(self.cooldown - elapsed));
+            }
+            transition = breaker.enter(BreakerState::HalfOpen);
+        }
+
+        let probe = breaker.state == BreakerState::HalfOpen;
+        let admitted = !probe || breaker.probes < self.probes;
+        if probe && admitted {
+            breaker.probes += 1;
+        }
+        drop(breaker);
+        self.notify(transition);
+
+        if !admitted {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(Error::CircuitOpen(Duration::ZERO));
+        }
+        Ok(Permit { channel: self, probe, settled: false })
+    }
+
+    fn record(&self, probe: bool, failed: bool) {
+        let mut breaker = self.breaker.lock();
+        let transition = match (probe, breaker.state) {
+            (true, BreakerState::HalfOpen) => {
+                breaker.probes = breaker.probes.saturating_sub(1);
+                if failed {
+                    breaker.enter(BreakerState::Open)
+                } else {
+                    breaker.probes_succeeded += 1;
+                    match breaker.probes_succeeded >= self.probes {
+                        true => breaker.enter(BreakerState::Closed),
+                        false => None,
+                    }
+                }
+            }
+            (false, BreakerState::Closed) => {
+                breaker.consecutive_failures = if failed { breaker.consecutive_failures + 1 } else { 0 };
+                if let Some((_, window)) = self.error_rate {
+                    if breaker.outcomes.len() == window {
+                        breaker.outcomes.pop_front();
+                    }
+                    breaker.outcomes.push_back(failed);
+                }
+
+                let tripped = breaker.consecutive_failures >= self.failure_threshold
+                    || self.error_rate.is_some_and(|(rate, window)| {
+                        let failures = breaker.outcomes.iter().filter(|failed| **failed).count();
+                        breaker.outcomes.len() == window && failures as f64 >= rate * window as f64
+                    });
+                match tripped {
+                    true => breaker.enter(BreakerState::Open),
+                    false => None,
+                }
+            }
+            // Calls admitted before the last transition no longer count.
+            _ => None,
+        };
+        drop(breaker);
+        self.notify(transition);
+    }
+
+    fn failed(&self, result: &Result<Response, Error>) -> bool {
+        match result {
+            Ok(response) => response.error.as_ref()
+                .is_some_and(|error| self.policy.retryable(&Failure::JsonRpc(error))),
+            Err(err) => self.failed_call(err),
+        }
+    }
+
+    // Calls that ran into the timeout count whatever the policy says.
+    fn failed_call(&self, err: &Error) -> bool {
+        matches!(err, Error::DeadlineExceeded(_)) || self.policy.retryable(&Failure::Error(err))
+    }
+
+    async fn call<T, F>(&self, call: F) -> Result<T, Error>
+    where
+        F: std::future::Future<Output=Result<T, Error>>,
+    {
+        tokio::time::timeout(self.call_timeout, call).await
+            .unwrap_or(Err(Error::DeadlineExceeded(self.call_timeout)))
+    }
+}
+
+// A call let through the breaker. A probe dropped before its outcome is known frees its slot.
+struct Permit<'a, C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    channel: &'a CircuitBreakerChannel<C>,
+    probe: bool,
+    settled: bool,
+}
+
+impl<C> Permit<'_, C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    fn settle(mut self, failed: bool) {
+        self.settled = true;
+        self.channel.record(self.probe, failed);
+    }
+}
+
+impl<C> Drop for Permit<'_, C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    fn drop(&mut self) {
+        if !self.settled && self.probe {
+            let mut breaker = self.channel.breaker.lock();
+            if breaker.state == BreakerState::HalfOpen {
+                breaker.probes = breaker.probes.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C> OneshotChannel for CircuitBreakerChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        let permit = self.admit()?;
+        let result = self.call(self.inner.fire(jsonrpc)).await;
+        permit.settle(self.failed(&result));
+        result
+    }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let permit = self.admit()?;
+        let result = self.call(self.inner.fire_batch(batch)).await;
+        permit.settle(result.as_ref().is_err_and(|err| self.failed_call(err)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn unreachable() -> Result<Response, Error> {
+        Err(Error::ConnectionError("http://dead".to_string()))
+    }
+
+    fn reverted() -> Result<Response, Error> {
+        let error = json!({ "code": 3, "message": "execution reverted" });
+        Ok(Response { id: Id::Num(1), result: None, error: Some(error) })
+    }
+
+    fn jsonrpc() -> JsonRpc {
+        JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null))
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_and_closes() {
+        let inner = MockChannel::new().failing(unreachable);
+        let down = inner.down.clone();
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let channel = CircuitBreakerChannel::new(inner)
+            .with_failure_threshold(3)
+            .with_cooldown(Duration::from_millis(50))
+            .on_transition({
+                let transitions = transitions.clone();
+                move |previous, current| transitions.lock().push((previous, current))
+            });
+
+        for _ in 0..3 {
+            assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::ConnectionError(_))));
+        }
+        assert_eq!(channel.state(), BreakerState::Open);
+        assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::CircuitOpen(_))));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 3);
+        assert_eq!(channel.rejected(), 1);
+
+        // A failing probe opens the circuit again.
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::ConnectionError(_))));
+        assert_eq!(channel.state(), BreakerState::Open);
+
+        down.store(false, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(channel.fire(&jsonrpc()).await.is_ok());
+        assert_eq!(channel.state(), BreakerState::Closed);
+
+        assert_eq!(*transitions.lock(), vec![
+            (BreakerState::Closed, BreakerState::Open),
+            (BreakerState::Open, BreakerState::HalfOpen),
+            (BreakerState::HalfOpen, BreakerState::Open),
+            (BreakerState::Open, BreakerState::HalfOpen),
+            (BreakerState::HalfOpen, BreakerState::Closed),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_error_rate() {
+        let inner = MockChannel::new().failing(unreachable);
+        let down = inner.down.clone();
+        let channel = CircuitBreakerChannel::new(inner)
+            .with_failure_threshold(100)
+            .with_error_rate(0.5, 4);
+
+        // Alternating failures never make a streak but do make half of the window.
+        for call in 0..4 {
+            down.store(call % 2 == 0, Ordering::Relaxed);
+            let _ = channel.fire(&jsonrpc()).await;
+            assert_eq!(channel.state(), if call < 3 { BreakerState::Closed } else { BreakerState::Open });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaker_ignores_deterministic_errors() {
+        let inner = MockChannel::new().failing(reverted);
+        let channel = CircuitBreakerChannel::new(inner).with_failure_threshold(2);
+
+        for _ in 0..5 {
+            assert!(channel.fire(&jsonrpc()).await.unwrap().error.is_some());
+        }
+        assert_eq!(channel.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_call_timeout() {
+        let inner = MockChannel::new().with_latency(Duration::from_secs(3600));
+        let channel = CircuitBreakerChannel::new(inner)
+            .with_failure_threshold(2)
+            .with_call_timeout(Duration::from_millis(10));
+
+        for _ in 0..2 {
+            assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::DeadlineExceeded(_))));
+        }
+        assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::CircuitOpen(_))));
+    }
+}
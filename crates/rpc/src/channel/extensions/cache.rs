@@ -0,0 +1,411 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::Mutex;
+use crate::channel::OneshotChannel;
+use crate::jsonrpc::{normalize, Id, JsonRpc, Response, Tag};
+use crate::Error;
+
+// Identifies a cached result. Params are kept as their normalized JSON text, which is stable
+// since object keys are ordered and hex is lowercased.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey {
+    pub method: String,
+    pub params: String,
+}
+
+impl CacheKey {
+    pub fn new(jsonrpc: &JsonRpc) -> Self {
+        Self {
+            method: jsonrpc.method.clone(),
+            params: normalize(&jsonrpc.params).to_string(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value>;
+    async fn put(&self, key: CacheKey, value: serde_json::Value);
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<CacheKey, (serde_json::Value, u64)>,
+    // Entries by last use, oldest first.
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &CacheKey) -> Option<serde_json::Value> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (value, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        *used = tick;
+        self.order.insert(tick, key.clone());
+        Some(value.clone())
+    }
+}
+
+// Keeps up to `capacity` results in memory, evicting the least recently used.
+pub struct LruStore {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl LruStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lru.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lru.lock().entries.is_empty()
+    }
+}
+
+#[async_trait]
+impl CacheStore for LruStore {
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        self.lru.lock().touch(key)
+    }
+
+    async fn put(&self, key: CacheKey, value: serde_json::Value) {
+        let mut lru = self.lru.lock();
+        if lru.touch(&key).is_some() {
+            lru.entries.get_mut(&key).unwrap().0 = value;
+            return;
+        }
+
+        if lru.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = lru.order.pop_first() {
+                lru.entries.remove(&oldest);
+            }
+        }
+        let tick = lru.tick;
+        lru.order.insert(tick, key.clone());
+        lru.entries.insert(key, (value, tick));
+    }
+}
+
+// When a method's result may be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheRule {
+    // The result never changes, like the chain id.
+    Always,
+    // The block parameter at this position is a finalized block number, or an EIP-1898
+    // `blockHash`, which pins the state regardless of finality. Tags such as `latest` and `pending`
+    // are never cached.
+    AtBlock(usize),
+    // The result names the block it was mined in, as `blockNumber`, or is a block whose `number`
+    // is finalized.
+    Mined,
+}
+
+// Caches results that can no longer change. Block numbers count as final once they are
+// `confirmations` behind the highest `eth_blockNumber` seen, or behind the number given to
+// `set_finalized`. Errors and null results are never cached.
+pub struct CachingChannel<C> {
+    inner: C,
+    store: Box<dyn CacheStore>,
+    rules: HashMap<String, CacheRule>,
+    confirmations: u64,
+    finalized: Mutex<Option<u64>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<C> CachingChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    pub fn new(inner: C) -> Self {
+        Self::with_store(inner, LruStore::new(10_000))
+    }
+
+    pub fn with_store<S>(inner: C, store: S) -> Self
+    where
+        S: CacheStore + 'static,
+    {
+        let rules = [
+            ("eth_chainId", CacheRule::Always),
+            ("net_version", CacheRule::Always),
+            ("eth_getBlockByNumber", CacheRule::AtBlock(0)),
+            ("eth_getBalance", CacheRule::AtBlock(1)),
+            ("eth_getCode", CacheRule::AtBlock(1)),
+            ("eth_getTransactionCount", CacheRule::AtBlock(1)),
+            ("eth_call", CacheRule::AtBlock(1)),
+            ("eth_getStorageAt", CacheRule::AtBlock(2)),
+            ("eth_getBlockByHash", CacheRule::Mined),
+            ("eth_getTransactionByHash", CacheRule::Mined),
+            ("eth_getTransactionReceipt", CacheRule::Mined),
+        ];
+
+        Self {
+            inner,
+            store: Box::new(store),
+            rules: rules.into_iter().map(|(method, rule)| (method.to_string(), rule)).collect(),
+            confirmations: 64,
+            finalized: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_rule<M>(mut self, method: M, rule: CacheRule) -> Self
+    where
+        M: Into<String>,
+    {
+        self.rules.insert(method.into(), rule);
+        self
+    }
+
+    pub fn without_rule(mut self, method: &str) -> Self {
+        self.rules.remove(method);
+        self
+    }
+
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    pub fn set_finalized(&self, number: u64) {
+        let mut finalized = self.finalized.lock();
+        *finalized = Some(finalized.map_or(number, |finalized| finalized.max(number)));
+    }
+
+    pub fn finalized(&self) -> Option<u64> {
+        *self.finalized.lock()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn is_final(&self, number: u64) -> bool {
+        self.finalized().is_some_and(|finalized| number <= finalized)
+    }
+
+    // Whether the request could be answered from the cache at all, judging by its params alone.
+    fn lookup(&self, jsonrpc: &JsonRpc) -> Option<CacheKey> {
+        jsonrpc.id.as_ref()?;
+        match self.rules.get(&jsonrpc.method)? {
+            CacheRule::Always | CacheRule::Mined => Some(CacheKey::new(jsonrpc)),
+            CacheRule::AtBlock(position) => {
+                let param = jsonrpc.params.get(position)?;
+                if param.get("blockHash").is_some() {
+                    return Some(CacheKey::new(jsonrpc));
+                }
+                match Tag::from_param(param)? {
+                    Tag::Earliest => Some(CacheKey::new(jsonrpc)),
+                    Tag::Block(number) if self.is_final(number) => Some(CacheKey::new(jsonrpc)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn cacheable(&self, jsonrpc: &JsonRpc, result: &serde_json::Value) -> bool {
+        match self.rules.get(&jsonrpc.method) {
+            Some(CacheRule::Mined) => result.get("blockNumber")
+                .or_else(|| result.get("number"))
+                .and_then(Tag::from_param)
+                .is_some_and(|tag| matches!(tag, Tag::Block(number) if self.is_final(number))),
+            Some(_) => !result.is_null(),
+            None => false,
+        }
+    }
+
+    async fn cached(&self, jsonrpc: &JsonRpc) -> Option<Response> {
+        let key = self.lookup(jsonrpc)?;
+        let result = self.store.get(&key).await;
+        match result {
+            Some(result) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(Response { id: jsonrpc.id.clone()?, result: Some(result), error: None })
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    async fn record(&self, jsonrpc: &JsonRpc, response: &Response) {
+        let result = match response {
+            Response { result: Some(result), error: None, .. } => result,
+            _ => return,
+        };
+
+        if jsonrpc.method == "eth_blockNumber" {
+            if let Some(Tag::Block(head)) = Tag::from_param(result) {
+                self.set_finalized(head.saturating_sub(self.confirmations));
+            }
+        }
+
+        if self.lookup(jsonrpc).is_some() && self.cacheable(jsonrpc, result) {
+            self.store.put(CacheKey::new(jsonrpc), result.clone()).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<C> OneshotChannel for CachingChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        if let Some(response) = self.cached(jsonrpc).await {
+            return Ok(response);
+        }
+
+        let response = self.inner.fire(jsonrpc).await?;
+        self.record(jsonrpc, &response).await;
+        Ok(response)
+    }
+
+    // Cached requests are answered in place and only the rest go out, still as one batch.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let mut responses = Vec::with_capacity(batch.len());
+        for jsonrpc in batch {
+            responses.push(self.cached(jsonrpc).await.map(Ok));
+        }
+        let missing = batch.iter().zip(&responses)
+            .filter(|(_, response)| response.is_none())
+            .map(|(jsonrpc, _)| jsonrpc.clone())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(responses.into_iter().flatten().collect());
+        }
+
+        let mut fetched = self.inner.fire_batch(&missing).await?.into_iter();
+        for (jsonrpc, slot) in batch.iter().zip(responses.iter_mut()) {
+            if slot.is_none() {
+                let missing = || Error::MissingResponse(jsonrpc.id.clone().unwrap_or(Id::Num(0)));
+                let result = fetched.next().unwrap_or_else(|| Err(missing()));
+                if let Ok(response) = &result {
+                    self.record(jsonrpc, response).await;
+                }
+                *slot = Some(result);
+            }
+        }
+        Ok(responses.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+
+    // Answers with the params it was called with, apart from a few methods with fixed answers.
+    fn echo() -> MockChannel {
+        MockChannel::new().with_answer(|jsonrpc| match jsonrpc.method.as_str() {
+            "eth_chainId" => json!("0x1"),
+            "eth_blockNumber" => json!("0x100"),
+            "eth_getTransactionReceipt" if jsonrpc.params[0] == "0xpending" => json!(null),
+            "eth_getTransactionReceipt" => json!({ "blockNumber": jsonrpc.params[1], "status": "0x1" }),
+            "eth_getBlockByHash" => json!({ "hash": jsonrpc.params[0], "number": jsonrpc.params[1] }),
+            _ => jsonrpc.params.clone(),
+        })
+    }
+
+    fn call(id: u64, method: &'static str, params: serde_json::Value) -> JsonRpc {
+        JsonRpc::format(Id::Num(id), method, params)
+    }
+
+    async fn fire_twice(channel: &CachingChannel<MockChannel>, method: &'static str, params: serde_json::Value) -> usize {
+        let before = channel.inner().calls.load(Ordering::Relaxed);
+        for id in 0..2 {
+            let response = channel.fire(&call(id, method, params.clone())).await.unwrap();
+            assert_eq!(response.id, Id::Num(id));
+        }
+        channel.inner().calls.load(Ordering::Relaxed) - before
+    }
+
+    #[tokio::test]
+    async fn test_caching_rules() {
+        let channel = CachingChannel::new(echo()).with_confirmations(16);
+
+        assert_eq!(fire_twice(&channel, "eth_chainId", json!(null)).await, 1);
+        assert_eq!(fire_twice(&channel, "eth_gasPrice", json!(null)).await, 2);
+
+        // Fixed block numbers are only cached once final.
+        assert_eq!(fire_twice(&channel, "eth_getBlockByNumber", json!(["0x10", false])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_blockNumber", json!(null)).await, 2);
+        assert_eq!(channel.finalized(), Some(0x100 - 16));
+        assert_eq!(fire_twice(&channel, "eth_getBlockByNumber", json!(["0x10", false])).await, 1);
+        assert_eq!(fire_twice(&channel, "eth_getBlockByNumber", json!(["0xff", false])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_call", json!([{ "to": "0x01" }, { "blockNumber": "0x10" }])).await, 1);
+        assert_eq!(fire_twice(&channel, "eth_call", json!([{ "to": "0x02" }, { "blockNumber": "0xff" }])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_call", json!([{ "to": "0x01" }, { "blockHash": "0xaa", "requireCanonical": true }])).await, 1);
+        assert_eq!(fire_twice(&channel, "eth_call", json!([{ "to": "0x01" }, "latest"])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_getBalance", json!(["0x01", "pending"])).await, 2);
+
+        // Receipts are cached once mined in a final block.
+        assert_eq!(fire_twice(&channel, "eth_getTransactionReceipt", json!(["0xpending"])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_getTransactionReceipt", json!(["0xaa", "0xff"])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_getTransactionReceipt", json!(["0xbb", "0x20"])).await, 1);
+
+        // Blocks give their own number.
+        assert_eq!(fire_twice(&channel, "eth_getBlockByHash", json!(["0xcc", "0xff"])).await, 2);
+        assert_eq!(fire_twice(&channel, "eth_getBlockByHash", json!(["0xdd", "0x20"])).await, 1);
+
+        assert_eq!(channel.hits(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_caching_batch() {
+        let channel = CachingChannel::new(echo()).with_rule("eth_custom", CacheRule::Always);
+        channel.fire(&call(1, "eth_custom", json!(["a"]))).await.unwrap();
+
+        let batch = vec![
+            call(2, "eth_custom", json!(["a"])),
+            call(3, "eth_custom", json!(["b"])),
+            call(4, "eth_gasPrice", json!(null)),
+        ];
+        let responses = channel.fire_batch(&batch).await.unwrap();
+        let results = responses.into_iter().map(|response| response.unwrap()).collect::<Vec<_>>();
+        assert_eq!(results.iter().map(|response| response.id.clone()).collect::<Vec<_>>(), vec![Id::Num(2), Id::Num(3), Id::Num(4)]);
+        assert_eq!(results[0].result, Some(json!(["a"])));
+        assert_eq!(results[1].result, Some(json!(["b"])));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 3);
+
+        channel.fire_batch(&batch[..2]).await.unwrap();
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_lru_store() {
+        let store = LruStore::new(2);
+        let key = |method: &str| CacheKey { method: method.to_string(), params: "null".to_string() };
+
+        store.put(key("a"), json!(1)).await;
+        store.put(key("b"), json!(2)).await;
+        assert_eq!(store.get(&key("a")).await, Some(json!(1)));
+        store.put(key("c"), json!(3)).await;
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&key("b")).await, None);
+        assert_eq!(store.get(&key("a")).await, Some(json!(1)));
+        assert_eq!(store.get(&key("c")).await, Some(json!(3)));
+    }
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+use crate::channel::OneshotChannel;
+use crate::channel::extensions::{Failure, RetryPolicy, TransientPolicy};
+use crate::jsonrpc::{JsonRpc, Response};
+use crate::Error;
+
+struct Provider {
+    channel: Box<dyn OneshotChannel<Output=Response>>,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Provider {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.lock().is_none_or(|until| until <= now)
+    }
+}
+
+// Tries channels in the order they were added, moving on when the policy deems a failure worth
+// failing over for, and always when a channel's circuit is open. A failing channel is skipped for
+// the cool-down period, then tried first again.
+pub struct FallbackChannel {
+    providers: Vec<Provider>,
+    cooldown: Duration,
+    policy: Box<dyn RetryPolicy>,
+}
+
+impl Default for FallbackChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FallbackChannel {
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+            cooldown: Duration::from_secs(30),
+            policy: Box::new(TransientPolicy::default()),
+        }
+    }
+
+    pub fn with_channel<C>(mut self, channel: C) -> Self
+    where
+        C: OneshotChannel<Output=Response> + 'static,
+    {
+        self.providers.push(Provider {
+            channel: Box::new(channel),
+            unhealthy_until: Mutex::new(None),
+        });
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_policy<P>(mut self, policy: P) -> Self
+    where
+        P: RetryPolicy + 'static,
+    {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.providers.get(index).is_some_and(|provider| provider.is_healthy(Instant::now()))
+    }
+
+    // Healthy channels in priority order, then the ones cooling down as a last resort.
+    fn order(&self) -> Vec<&Provider> {
+        let now = Instant::now();
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = self.providers.iter()
+            .partition(|provider| provider.is_healthy(now));
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    fn record(&self, provider: &Provider, failed: bool) {
+        let mut unhealthy_until = provider.unhealthy_until.lock();
+        *unhealthy_until = failed.then(|| Instant::now() + self.cooldown);
+    }
+
+    // An open circuit is a reason to try the next channel even under a policy that won't retry it,
+    // since that is a different provider.
+    fn fails_over(&self, err: &Error) -> bool {
+        matches!(err, Error::CircuitOpen(_)) || self.policy.retryable(&Failure::Error(err))
+    }
+
+    fn exhausted(&self) -> Error {
+        Error::ConnectionError("no channel to fall back to".to_string())
+    }
+}
+
+#[async_trait]
+impl OneshotChannel for FallbackChannel {
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        let mut last = None;
+        for provider in self.order() {
+            let result = provider.channel.fire(jsonrpc).await;
+            let failed = match &result {
+                Ok(response) => response.error.as_ref()
+                    .is_some_and(|error| self.policy.retryable(&Failure::JsonRpc(error))),
+                Err(err) => self.fails_over(err),
+            };
+
+            self.record(provider, failed);
+            if !failed {
+                return result;
+            }
+            log::warn!("Falling back from failed channel for {}", jsonrpc.method);
+            last = Some(result);
+        }
+
+        last.unwrap_or_else(|| Err(self.exhausted()))
+    }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let mut last = None;
+        for provider in self.order() {
+            let result = provider.channel.fire_batch(batch).await;
+            let failed = match &result {
+                Ok(_) => false,
+                Err(err) => self.fails_over(err),
+            };
+
+            self.record(provider, failed);
+            if !failed {
+                return result;
+            }
+            log::warn!("Falling back from failed channel for batch of {}", batch.len());
+            last = Some(result);
+        }
+
+        last.unwrap_or_else(|| Err(self.exhausted()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use super::*;
+    use crate::channel::CircuitBreakerChannel;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn unreachable() -> Result<Response, Error> {
+        Err(Error::ConnectionError("http://primary".to_string()))
+    }
+
+    fn reverted() -> Result<Response, Error> {
+        let error = json!({ "code": 3, "message": "execution reverted" });
+        Ok(Response { id: Id::Num(1), result: None, error: Some(error) })
+    }
+
+    async fn answer(channel: &FallbackChannel) -> Result<String, Error> {
+        let jsonrpc = JsonRpc::format(Id::Num(1), "eth_chainId", json!(null));
+        let response = channel.fire(&jsonrpc).await?;
+        Ok(response.as_result::<String>()?.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_fallback_channel() {
+        let primary = MockChannel::named("primary").failing(unreachable);
+        let (down, calls) = (primary.down.clone(), primary.calls.clone());
+        down.store(false, Ordering::Relaxed);
+        let backup = MockChannel::named("backup");
+        let channel = FallbackChannel::new()
+            .with_channel(primary)
+            .with_channel(backup)
+            .with_cooldown(Duration::from_millis(50));
+
+        assert_eq!(answer(&channel).await.unwrap(), "primary");
+
+        down.store(true, Ordering::Relaxed);
+        assert_eq!(answer(&channel).await.unwrap(), "backup");
+        assert!(!channel.is_healthy(0));
+
+        // The primary is left alone while cooling down.
+        assert_eq!(answer(&channel).await.unwrap(), "backup");
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        down.store(false, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(answer(&channel).await.unwrap(), "primary");
+        assert!(channel.is_healthy(0));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_deterministic() {
+        let primary = MockChannel::named("primary").failing(reverted);
+        let backup = MockChannel::named("backup");
+        let calls = backup.calls.clone();
+        let channel = FallbackChannel::new()
+            .with_channel(primary)
+            .with_channel(backup);
+
+        assert!(matches!(answer(&channel).await, Err(Error::JsonRpcError(_))));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        assert!(channel.is_healthy(0));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_open_circuit() {
+        let primary = MockChannel::named("primary").failing(unreachable);
+        let calls = primary.calls.clone();
+        let channel = FallbackChannel::new()
+            .with_channel(CircuitBreakerChannel::new(primary).with_failure_threshold(1))
+            .with_channel(MockChannel::named("backup"))
+            .with_cooldown(Duration::from_millis(10));
+
+        assert_eq!(answer(&channel).await.unwrap(), "backup");
+
+        // Tried first again once cooled down, the primary's circuit is still open.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(answer(&channel).await.unwrap(), "backup");
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let batch = vec![JsonRpc::format(Id::Num(1), "eth_chainId", json!(null))];
+        let results = channel.fire_batch(&batch).await.unwrap();
+        assert_eq!(results[0].as_ref().unwrap().result, Some(json!("backup")));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_exhausted() {
+        let primary = MockChannel::named("primary").failing(unreachable);
+        let backup = MockChannel::named("backup").failing(unreachable);
+        let channel = FallbackChannel::new()
+            .with_channel(primary)
+            .with_channel(backup);
+
+        assert!(matches!(answer(&channel).await, Err(Error::ConnectionError(_))));
+        assert!(matches!(answer(&FallbackChannel::new()).await, Err(Error::ConnectionError(_))));
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+use crate::channel::OneshotChannel;
+use crate::jsonrpc::{self, JsonRpc, Response};
+use crate::Error;
+
+// Latencies kept for the adaptive delay, and how many are needed before relying on them.
+const SAMPLES: usize = 128;
+const MIN_SAMPLES: usize = 16;
+
+// How long to wait on the primary before also asking the secondary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HedgeDelay {
+    Fixed(Duration),
+    // The given percentile of the primary's recent latencies, or `initial` until there are enough.
+    Adaptive { percentile: f64, initial: Duration },
+}
+
+// Sends to the primary and, when it hasn't answered within the delay, to the secondary as well.
+// The first response wins and the other call is dropped. A failing channel hands over to the other
+// one at once rather than waiting out the delay. When the secondary wins, the time taken so far is
+// recorded as the primary's latency.
+pub struct HedgedChannel<P, S> {
+    primary: P,
+    secondary: S,
+    delay: HedgeDelay,
+    latencies: Mutex<VecDeque<Duration>>,
+    hedged: AtomicU64,
+}
+
+impl<P, S> HedgedChannel<P, S>
+where
+    P: OneshotChannel<Output=Response>,
+    S: OneshotChannel<Output=Response>,
+{
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            delay: HedgeDelay::Adaptive { percentile: 0.95, initial: Duration::from_millis(100) },
+            latencies: Mutex::new(VecDeque::with_capacity(SAMPLES)),
+            hedged: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_delay(mut self, delay: HedgeDelay) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    // How many calls went to the secondary as well.
+    pub fn hedged(&self) -> u64 {
+        self.hedged.load(Ordering::Relaxed)
+    }
+
+    pub fn delay(&self) -> Duration {
+        let (percentile, initial) = match self.delay {
+            HedgeDelay::Fixed(delay) => return delay,
+            HedgeDelay::Adaptive { percentile, initial } => (percentile, initial),
+        };
+
+        let latencies = self.latencies.lock();
+        if latencies.len() < MIN_SAMPLES {
+            return initial;
+        }
+        let mut sorted = latencies.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+        let rank = ((sorted.len() as f64 * percentile.clamp(0.0, 1.0)).ceil() as usize).clamp(1, sorted.len());
+        sorted[rank - 1]
+    }
+
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock();
+        if latencies.len() == SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    // Races `primary` against the call `secondary` makes once the delay is up. Only calls for
+    // which `sampled` is set feed their latency to the adaptive delay.
+    async fn hedge<T, F, G>(&self, what: &str, sampled: bool, primary: F, secondary: impl FnOnce() -> G) -> Result<T, Error>
+    where
+        F: Future<Output=Result<T, Error>>,
+        G: Future<Output=Result<T, Error>>,
+    {
+        let started = Instant::now();
+        let record = |result: &Result<T, Error>| if sampled && result.is_ok() {
+            self.record(started.elapsed());
+        };
+        tokio::pin!(primary);
+
+        tokio::select! {
+            result = &mut primary => {
+                if result.is_ok() {
+                    record(&result);
+                    return result;
+                }
+                log::warn!("Primary failed for {}, trying the secondary", what);
+                return secondary().await;
+            }
+            _ = tokio::time::sleep(self.delay()) => {}
+        }
+
+        self.hedged.fetch_add(1, Ordering::Relaxed);
+        let secondary = secondary();
+        tokio::pin!(secondary);
+
+        tokio::select! {
+            result = &mut primary => match result {
+                Ok(_) => {
+                    record(&result);
+                    result
+                }
+                Err(_) => secondary.await,
+            },
+            result = &mut secondary => match result {
+                // The primary would have taken at least this long, and leaving it out would keep
+                // the delay down at the latencies of the calls it did win.
+                Ok(_) => {
+                    record(&result);
+                    result
+                }
+                Err(_) => {
+                    let result = primary.await;
+                    record(&result);
+                    result
+                }
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<P, S> OneshotChannel for HedgedChannel<P, S>
+where
+    P: OneshotChannel<Output=Response>,
+    S: OneshotChannel<Output=Response>,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        self.hedge(&jsonrpc.method, true, self.primary.fire(jsonrpc), || self.secondary.fire(jsonrpc)).await
+    }
+
+    // The batch is hedged as a whole. How long it takes depends on its size, so it is left out of
+    // the adaptive delay.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        jsonrpc::validate_batch(batch)?;
+
+        let what = format!("a batch of {}", batch.len());
+        self.hedge(&what, false, self.primary.fire_batch(batch), || self.secondary.fire_batch(batch)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn slow(name: &'static str, latency: Duration) -> MockChannel {
+        MockChannel::named(name).with_latency(latency)
+    }
+
+    async fn answer<P, S>(channel: &HedgedChannel<P, S>) -> Result<String, Error>
+    where
+        P: OneshotChannel<Output=Response>,
+        S: OneshotChannel<Output=Response>,
+    {
+        let jsonrpc = JsonRpc::format(Id::Num(1), "eth_call", json!(null));
+        Ok(channel.fire(&jsonrpc).await?.as_result::<String>()?.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_hedged_fast_primary() {
+        let channel = HedgedChannel::new(slow("primary", Duration::ZERO), slow("secondary", Duration::ZERO))
+            .with_delay(HedgeDelay::Fixed(Duration::from_millis(50)));
+
+        assert_eq!(answer(&channel).await.unwrap(), "primary");
+        assert_eq!(channel.secondary().calls.load(Ordering::Relaxed), 0);
+        assert_eq!(channel.hedged(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_hedged_slow_primary() {
+        let channel = HedgedChannel::new(slow("primary", Duration::from_millis(200)), slow("secondary", Duration::ZERO))
+            .with_delay(HedgeDelay::Fixed(Duration::from_millis(20)));
+
+        let started = Instant::now();
+        assert_eq!(answer(&channel).await.unwrap(), "secondary");
+        assert!(started.elapsed() < Duration::from_millis(150));
+        assert_eq!(channel.hedged(), 1);
+
+        // The primary's call was dropped once the secondary answered.
+        let finished = channel.primary().finished.clone();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(finished.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_hedged_batch() {
+        let channel = HedgedChannel::new(slow("primary", Duration::from_millis(200)), slow("secondary", Duration::ZERO))
+            .with_delay(HedgeDelay::Fixed(Duration::from_millis(20)));
+        let batch = vec![
+            JsonRpc::format(Id::Num(1), "eth_chainId", json!(null)),
+            JsonRpc::format(Id::Num(2), "eth_blockNumber", json!(null)),
+        ];
+
+        let results = channel.fire_batch(&batch).await.unwrap();
+        let answers = results.into_iter()
+            .map(|result| result.unwrap().as_result::<String>().unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(answers, vec!["secondary", "secondary"]);
+        assert_eq!(channel.hedged(), 1);
+
+        // Each channel got the batch in one call, and its latency was not sampled.
+        assert_eq!(*channel.primary().batches.lock(), vec![2]);
+        assert_eq!(*channel.secondary().batches.lock(), vec![2]);
+        assert!(channel.latencies.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hedged_failures() {
+        let primary = slow("primary", Duration::ZERO).failing(|| Err(Error::ConnectionError("primary".to_string())));
+        let channel = HedgedChannel::new(primary, slow("secondary", Duration::ZERO))
+            .with_delay(HedgeDelay::Fixed(Duration::from_secs(10)));
+        assert_eq!(answer(&channel).await.unwrap(), "secondary");
+
+        let secondary = slow("secondary", Duration::ZERO).failing(|| Err(Error::ConnectionError("secondary".to_string())));
+        let channel = HedgedChannel::new(slow("primary", Duration::from_millis(30)), secondary)
+            .with_delay(HedgeDelay::Fixed(Duration::from_millis(5)));
+        assert_eq!(answer(&channel).await.unwrap(), "primary");
+    }
+
+    #[tokio::test]
+    async fn test_hedged_adaptive_delay() {
+        let channel = HedgedChannel::new(slow("primary", Duration::ZERO), slow("secondary", Duration::ZERO))
+            .with_delay(HedgeDelay::Adaptive { percentile: 0.9, initial: Duration::from_millis(100) });
+        assert_eq!(channel.delay(), Duration::from_millis(100));
+
+        for millis in 1..=20 {
+            channel.record(Duration::from_millis(millis));
+        }
+        assert_eq!(channel.delay(), Duration::from_millis(18));
+
+        for _ in 0..SAMPLES {
+            channel.record(Duration::from_millis(5));
+        }
+        assert_eq!(channel.delay(), Duration::from_millis(5));
+    }
+
+    #[tokio::test]
+    async fn test_hedged_adaptive_slow_primary() {
+        let initial = Duration::from_millis(10);
+        let channel = HedgedChannel::new(slow("primary", Duration::from_secs(10)), slow("secondary", Duration::from_millis(5)))
+            .with_delay(HedgeDelay::Adaptive { percentile: 0.5, initial });
+
+        for _ in 0..MIN_SAMPLES {
+            assert_eq!(answer(&channel).await.unwrap(), "secondary");
+        }
+        assert!(channel.delay() >= initial + Duration::from_millis(5));
+
+        // Each win of the secondary pushes it further up.
+        let delay = channel.delay();
+        for _ in 0..MIN_SAMPLES {
+            answer(&channel).await.unwrap();
+        }
+        assert!(channel.delay() > delay);
+    }
+}
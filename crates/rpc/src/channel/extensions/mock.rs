@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use crate::channel::OneshotChannel;
+use crate::jsonrpc::{self, Id, JsonRpc, Response};
+use crate::Error;
+
+type Answer = Box<dyn Fn(&JsonRpc) -> serde_json::Value + Send + Sync>;
+
+// A channel for testing the extensions. It plays back the scripted outcomes first, then fails the
+// way it is told to while `down` is set, and otherwise answers after the latency. The counters are
+// shared so they can still be read once the channel has been moved into the one under test.
+pub(crate) struct MockChannel {
+    answer: Answer,
+    outcomes: Mutex<VecDeque<Result<Response, Error>>>,
+    failure: Option<fn() -> Result<Response, Error>>,
+    latency: Duration,
+    // Answers no more than this many requests of a batch, as some providers do.
+    batch_limit: usize,
+    pub(crate) down: Arc<AtomicBool>,
+    // Requests received, counting each one in a batch.
+    pub(crate) calls: Arc<AtomicUsize>,
+    // Calls that ran to the end rather than being dropped.
+    pub(crate) finished: Arc<AtomicUsize>,
+    // The most calls it had at once.
+    pub(crate) peak: Arc<AtomicUsize>,
+    // The number of requests in each call, 1 for a single request.
+    pub(crate) batches: Arc<Mutex<Vec<usize>>>,
+    active: AtomicUsize,
+}
+
+impl MockChannel {
+    // Answers "0x1".
+    pub(crate) fn new() -> Self {
+        Self {
+            answer: Box::new(|_| json!("0x1")),
+            outcomes: Mutex::new(VecDeque::new()),
+            failure: None,
+            latency: Duration::ZERO,
+            batch_limit: usize::MAX,
+            down: Arc::default(),
+            calls: Arc::default(),
+            finished: Arc::default(),
+            peak: Arc::default(),
+            batches: Arc::default(),
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    // Answers with its name.
+    pub(crate) fn named(name: &'static str) -> Self {
+        Self::new().with_result(json!(name))
+    }
+
+    pub(crate) fn with_result(self, result: serde_json::Value) -> Self {
+        self.with_answer(move |_| result.clone())
+    }
+
+    pub(crate) fn with_answer<F>(mut self, answer: F) -> Self
+    where
+        F: Fn(&JsonRpc) -> serde_json::Value + Send + Sync + 'static,
+    {
+        self.answer = Box::new(answer);
+        self
+    }
+
+    pub(crate) fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub(crate) fn with_batch_limit(mut self, batch_limit: usize) -> Self {
+        self.batch_limit = batch_limit;
+        self
+    }
+
+    pub(crate) fn with_outcomes(self, outcomes: Vec<Result<Response, Error>>) -> Self {
+        *self.outcomes.lock() = outcomes.into();
+        self
+    }
+
+    // Fails with `failure` from the start, until `down` is cleared.
+    pub(crate) fn failing(mut self, failure: fn() -> Result<Response, Error>) -> Self {
+        self.failure = Some(failure);
+        self.down.store(true, Ordering::Relaxed);
+        self
+    }
+
+    fn respond(&self, jsonrpc: &JsonRpc) -> Result<Response, Error> {
+        if let Some(outcome) = self.outcomes.lock().pop_front() {
+            return outcome;
+        }
+        match self.failure {
+            Some(failure) if self.down.load(Ordering::Relaxed) => failure(),
+            _ => {
+                let id = jsonrpc.id.clone().unwrap_or(Id::Num(0));
+                Ok(Response { id, result: Some((self.answer)(jsonrpc)), error: None })
+            }
+        }
+    }
+
+    async fn call<T, F>(&self, requests: usize, respond: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.calls.fetch_add(requests, Ordering::Relaxed);
+        self.batches.lock().push(requests);
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(active, Ordering::SeqCst);
+
+        tokio::time::sleep(self.latency).await;
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.finished.fetch_add(1, Ordering::Relaxed);
+        respond()
+    }
+}
+
+#[async_trait]
+impl OneshotChannel for MockChannel {
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        self.call(1, || self.respond(jsonrpc)).await
+    }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        self.call(batch.len(), || {
+            jsonrpc::validate_batch(batch)?;
+            Ok(batch.iter().take(self.batch_limit).map(|jsonrpc| self.respond(jsonrpc)).collect())
+        }).await
+    }
+}
@@ -1,3 +1,25 @@
-pub use roundrobin::RoundRobinChannel;
+pub use balancer::{BalancedChannel, ProviderId};
+pub use retry::{RetryChannel, RetryPolicy, TransientPolicy, Failure};
+pub use fallback::FallbackChannel;
+pub use quorum::{QuorumChannel, Threshold};
+pub use rate::{RateLimitedChannel, Overflow};
+pub use cache::{CachingChannel, CacheStore, CacheKey, CacheRule, LruStore};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+pub use batching::BatchingChannel;
+pub use hedged::{HedgedChannel, HedgeDelay};
+pub use breaker::{CircuitBreakerChannel, BreakerState};
 
-mod roundrobin;
+mod balancer;
+mod retry;
+mod fallback;
+mod quorum;
+mod rate;
+mod cache;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod batching;
+mod hedged;
+mod breaker;
+#[cfg(test)]
+mod mock;
@@ -0,0 +1,405 @@
+use std::time::Duration;
+use futures::stream::{FuturesUnordered, StreamExt};
+use crate::channel::OneshotChannel;
+use crate::jsonrpc::{self, normalize, JsonRpc, Response};
+use crate::Error;
+
+// How much agreement a result needs before it is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    // More than half of the total weight.
+    Majority,
+    // Every channel, so a single failure or mismatch is enough to refuse.
+    All,
+    // At least this much weight. When that is no more than half of the total, two different
+    // results can both reach it, and that counts as no quorum.
+    Weighted(u64),
+}
+
+struct Provider {
+    name: String,
+    weight: u64,
+    channel: Box<dyn OneshotChannel<Output=Response>>,
+}
+
+// Sends each request to every channel and returns a result only when enough of them agree on it.
+// Results are normalized before comparing so that equal values spelled differently still match.
+// A null result is a vote like any other, and so is a JSON-RPC error: when enough providers return
+// the same error object, such as a revert, that error is the answer. It is returned as soon as no
+// other answer can reach the threshold any more, without waiting for the remaining providers.
+pub struct QuorumChannel {
+    providers: Vec<Provider>,
+    threshold: Threshold,
+    normalize: fn(&serde_json::Value) -> serde_json::Value,
+    timeout: Duration,
+}
+
+impl QuorumChannel {
+    pub fn new(threshold: Threshold) -> Self {
+        Self {
+            providers: Vec::new(),
+            threshold,
+            normalize,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_channel<N, C>(self, name: N, channel: C) -> Self
+    where
+        N: Into<String>,
+        C: OneshotChannel<Output=Response> + 'static,
+    {
+        self.with_weighted_channel(name, channel, 1)
+    }
+
+    pub fn with_weighted_channel<N, C>(mut self, name: N, channel: C, weight: u64) -> Self
+    where
+        N: Into<String>,
+        C: OneshotChannel<Output=Response> + 'static,
+    {
+        self.providers.push(Provider {
+            name: name.into(),
+            weight,
+            channel: Box::new(channel),
+        });
+        self
+    }
+
+    pub fn with_normalizer(mut self, normalize: fn(&serde_json::Value) -> serde_json::Value) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    // How long each provider has to answer before it counts as failed. Defaults to 30 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn reached(&self, weight: u64) -> bool {
+        let total = self.providers.iter().map(|provider| provider.weight).sum::<u64>();
+        match self.threshold {
+            Threshold::Majority => weight * 2 > total,
+            Threshold::All => weight == total,
+            Threshold::Weighted(required) => weight >= required,
+        }
+    }
+
+    // The members of the only group to reach the threshold, once neither another group nor the
+    // providers yet to answer can still reach it with the weight that is left.
+    fn decided<'a>(&self, groups: &'a [(Vote, u64, Vec<usize>)], remaining: u64) -> Option<&'a [usize]> {
+        let mut reached = groups.iter().enumerate().filter(|(_, (_, weight, _))| self.reached(*weight));
+        let (winner, members) = match (reached.next(), reached.next()) {
+            (Some((winner, (_, _, members))), None) => (winner, members),
+            _ => return None,
+        };
+
+        let contested = self.reached(remaining) || groups.iter().enumerate()
+            .any(|(index, (_, weight, _))| index != winner && self.reached(weight + remaining));
+        (!contested).then_some(members.as_slice())
+    }
+
+    // Sends the requests to every provider, as a batch or the one request on its own, and settles
+    // each request as soon as it is decided.
+    async fn gather(&self, requests: &[JsonRpc], batch: bool) -> Vec<Result<Response, Error>> {
+        let mut answers = self.providers.iter().enumerate()
+            .map(|(index, provider)| async move {
+                let fired = async {
+                    match batch {
+                        true => provider.channel.fire_batch(requests).await,
+                        false => Ok(vec![provider.channel.fire(&requests[0]).await]),
+                    }
+                };
+                let results = match tokio::time::timeout(self.timeout, fired).await {
+                    Ok(Ok(results)) => results,
+                    Ok(Err(err)) => requests.iter().map(|_| Err(err.duplicate())).collect(),
+                    Err(_) => requests.iter().map(|_| Err(Error::DeadlineExceeded(self.timeout))).collect(),
+                };
+                (index, results)
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut tallies = requests.iter().map(|_| Tally::new(self.providers.len())).collect::<Vec<_>>();
+        let mut remaining = self.providers.iter().map(|provider| provider.weight).sum::<u64>();
+        while let Some((index, results)) = answers.next().await {
+            remaining -= self.providers[index].weight;
+
+            // Providers may leave requests of a batch unanswered.
+            let mut results = results.into_iter();
+            for (tally, jsonrpc) in tallies.iter_mut().zip(requests) {
+                let result = results.next()
+                    .unwrap_or_else(|| Err(Error::MissingResponse(jsonrpc.id.clone().expect("validated batch"))));
+                if tally.outcome.is_none() {
+                    self.count(tally, index, result);
+                    self.settle(tally, remaining);
+                }
+            }
+
+            if tallies.iter().all(|tally| tally.outcome.is_some()) {
+                break;
+            }
+        }
+
+        tallies.into_iter()
+            .map(|tally| match tally.outcome {
+                Some(outcome) => outcome,
+                None => Err(self.no_quorum(tally)),
+            })
+            .collect()
+    }
+
+    fn count(&self, tally: &mut Tally, index: usize, result: Result<Response, Error>) {
+        let vote = match &result {
+            Ok(Response { error: Some(error), .. }) => Some(Vote::Error((self.normalize)(error))),
+            Ok(Response { result, .. }) => Some(Vote::Result(result.as_ref().map_or(serde_json::Value::Null, self.normalize))),
+            Err(_) => None,
+        };
+        tally.results[index] = Some(result);
+
+        if let Some(vote) = vote {
+            match tally.groups.iter_mut().find(|(value, _, _)| *value == vote) {
+                Some((_, weight, members)) => {
+                    *weight += self.providers[index].weight;
+                    members.push(index);
+                }
+                None => tally.groups.push((vote, self.providers[index].weight, vec![index])),
+            }
+        }
+    }
+
+    // Only a single group may reach the threshold, otherwise the providers disagree.
+    fn settle(&self, tally: &mut Tally, remaining: u64) {
+        if let Some(&first) = self.decided(&tally.groups, remaining).and_then(|members| members.first()) {
+            tally.outcome = tally.results[first].take();
+        }
+    }
+
+    // With no quorum, the largest group is reported as agreeing and everyone else against it.
+    fn no_quorum(&self, tally: Tally) -> Error {
+        let describe = |index: usize| {
+            let outcome = match &tally.results[index] {
+                Some(Ok(Response { error: Some(error), .. })) => format!("error {}", error),
+                Some(Ok(Response { result, .. })) => result.as_ref().map_or("null".to_string(), ToString::to_string),
+                Some(Err(err)) => err.to_string(),
+                None => "no answer".to_string(),
+            };
+            format!("{}: {}", self.providers[index].name, outcome)
+        };
+
+        let mut largest = tally.groups.iter()
+            .max_by_key(|(_, weight, _)| *weight)
+            .map(|(_, _, members)| members.clone())
+            .unwrap_or_default();
+        largest.sort_unstable();
+        let agreeing = largest.iter().map(|&index| self.providers[index].name.clone()).collect();
+        let disagreeing = (0..self.providers.len())
+            .filter(|index| !largest.contains(index))
+            .map(describe)
+            .collect();
+        Error::NoQuorum { agreeing, disagreeing }
+    }
+}
+
+#[async_trait]
+impl OneshotChannel for QuorumChannel {
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        self.gather(std::slice::from_ref(jsonrpc), false).await.pop().expect("one result per request")
+    }
+
+    // Every provider gets the whole batch, and each request in it needs a quorum of its own.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        jsonrpc::validate_batch(batch)?;
+        Ok(self.gather(batch, true).await)
+    }
+}
+
+// The votes on one request, grouped by the normalized answer given.
+struct Tally {
+    groups: Vec<(Vote, u64, Vec<usize>)>,
+    results: Vec<Option<Result<Response, Error>>>,
+    outcome: Option<Result<Response, Error>>,
+}
+
+impl Tally {
+    fn new(providers: usize) -> Self {
+        Self {
+            groups: Vec::new(),
+            results: (0..providers).map(|_| None).collect(),
+            outcome: None,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Vote {
+    Result(serde_json::Value),
+    Error(serde_json::Value),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn balance() -> JsonRpc {
+        JsonRpc::format(Id::Num(7), "eth_getBalance", json!(["0x01", "latest"]))
+    }
+
+    #[tokio::test]
+    async fn test_quorum_majority() {
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", MockChannel::new().with_result(json!("0xAB")))
+            .with_channel("b", MockChannel::new().with_result(json!("0xab")))
+            .with_channel("c", MockChannel::new().with_result(json!("0x01")));
+
+        let response = channel.fire(&balance()).await.unwrap();
+        assert_eq!(response.id, Id::Num(7));
+        assert_eq!(response.result, Some(json!("0xAB")));
+    }
+
+    #[tokio::test]
+    async fn test_quorum_all() {
+        let channel = QuorumChannel::new(Threshold::All)
+            .with_channel("a", MockChannel::new().with_result(json!({ "status": "0x1" })))
+            .with_channel("b", MockChannel::new().with_result(json!({ "status": "0x1" })))
+            .with_channel("c", MockChannel::new().failing(|| Err(Error::ConnectionError("http://c".to_string()))));
+
+        match channel.fire(&balance()).await {
+            Err(Error::NoQuorum { agreeing, disagreeing }) => {
+                assert_eq!(agreeing, vec!["a", "b"]);
+                assert_eq!(disagreeing, vec!["c: Connection error: http://c"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_weighted() {
+        let channel = QuorumChannel::new(Threshold::Weighted(3))
+            .with_weighted_channel("paid", MockChannel::new().with_result(json!("0x10")), 2)
+            .with_channel("public", MockChannel::new().with_result(json!("0x10")))
+            .with_channel("lagging", MockChannel::new().with_result(json!("0x0f")));
+        assert_eq!(channel.fire(&balance()).await.unwrap().result, Some(json!("0x10")));
+
+        let channel = QuorumChannel::new(Threshold::Weighted(3))
+            .with_weighted_channel("paid", MockChannel::new().with_result(json!("0x10")), 2)
+            .with_channel("lagging", MockChannel::new().with_result(json!("0x0f")));
+        match channel.fire(&balance()).await {
+            Err(Error::NoQuorum { agreeing, disagreeing }) => {
+                assert_eq!(agreeing, vec!["paid"]);
+                assert_eq!(disagreeing, vec!["lagging: \"0x0f\""]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_ambiguous() {
+        let channel = QuorumChannel::new(Threshold::Weighted(2))
+            .with_channel("a", MockChannel::new().with_result(json!("0x10")))
+            .with_channel("b", MockChannel::new().with_result(json!("0x10")))
+            .with_channel("c", MockChannel::new().with_result(json!("0x0f")))
+            .with_channel("d", MockChannel::new().with_result(json!("0x0f")));
+
+        match channel.fire(&balance()).await {
+            Err(Error::NoQuorum { agreeing, disagreeing }) => {
+                assert_eq!(agreeing, vec!["c", "d"]);
+                assert_eq!(disagreeing, vec!["a: \"0x10\"", "b: \"0x10\""]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_early() {
+        // The hung provider can no longer change the outcome, so it is not waited for.
+        let hung = MockChannel::new().with_latency(Duration::from_secs(3600));
+        let finished = hung.finished.clone();
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", MockChannel::new().with_result(json!("0x10")))
+            .with_channel("b", MockChannel::new().with_result(json!("0x10")).with_latency(Duration::from_millis(20)))
+            .with_channel("c", hung);
+
+        let response = tokio::time::timeout(Duration::from_secs(1), channel.fire(&balance())).await.unwrap();
+        assert_eq!(response.unwrap().result, Some(json!("0x10")));
+        assert_eq!(finished.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+        // Here it still could, until it times out.
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", MockChannel::new().with_result(json!("0x10")))
+            .with_channel("b", MockChannel::new().with_result(json!("0x0f")))
+            .with_channel("c", MockChannel::new().with_latency(Duration::from_secs(3600)))
+            .with_timeout(Duration::from_millis(50));
+
+        match channel.fire(&balance()).await {
+            Err(Error::NoQuorum { disagreeing, .. }) => {
+                assert!(disagreeing.contains(&"c: Deadline of 50ms exceeded".to_string()), "{:?}", disagreeing);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_batch() {
+        let by_method = |jsonrpc: &JsonRpc| json!(jsonrpc.method);
+        let a = MockChannel::new().with_answer(by_method);
+        let batches = a.batches.clone();
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", a)
+            .with_channel("b", MockChannel::new().with_answer(by_method).with_batch_limit(1))
+            .with_channel("c", MockChannel::new().with_answer(|jsonrpc| match jsonrpc.method.as_str() {
+                "eth_chainId" => json!("0x1"),
+                method => json!(method),
+            }));
+
+        let batch = vec![
+            JsonRpc::format(Id::Num(1), "eth_chainId", json!(null)),
+            JsonRpc::format(Id::Num(2), "eth_blockNumber", json!(null)),
+        ];
+        let results = channel.fire_batch(&batch).await.unwrap();
+        assert_eq!(*batches.lock(), vec![2]);
+
+        // a and b agree on the first request, a and c on the second, which b left unanswered.
+        assert_eq!(results[0].as_ref().unwrap().result, Some(json!("eth_chainId")));
+        assert_eq!(results[1].as_ref().unwrap().result, Some(json!("eth_blockNumber")));
+
+        let channel = QuorumChannel::new(Threshold::All)
+            .with_channel("a", MockChannel::new().with_answer(by_method))
+            .with_channel("b", MockChannel::new().with_answer(by_method).with_batch_limit(1));
+        let results = channel.fire_batch(&batch).await.unwrap();
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(Error::NoQuorum { agreeing, disagreeing }) => {
+                assert_eq!(agreeing, &vec!["a"]);
+                assert_eq!(disagreeing, &vec!["b: Missing response for id Num(2)"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_null_and_errors() {
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", MockChannel::new().with_result(json!(null)))
+            .with_channel("b", MockChannel::new().with_result(json!(null)))
+            .with_channel("c", MockChannel::new().with_result(json!("0x01")));
+        assert_eq!(channel.fire(&balance()).await.unwrap().result, Some(json!(null)));
+
+        let reverted = || {
+            let error = json!({ "code": 3, "message": "execution reverted" });
+            Ok(Response { id: Id::Num(7), result: None, error: Some(error) })
+        };
+        let channel = QuorumChannel::new(Threshold::Majority)
+            .with_channel("a", MockChannel::new().failing(reverted))
+            .with_channel("b", MockChannel::new().failing(reverted))
+            .with_channel("c", MockChannel::new().with_result(json!("0x01")));
+        let response = channel.fire(&balance()).await.unwrap();
+        assert_eq!(response.error, Some(json!({ "code": 3, "message": "execution reverted" })));
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+use crate::channel::OneshotChannel;
+use crate::jsonrpc::{JsonRpc, Response};
+use crate::Error;
+
+// What to do with a call once the budget is spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    // Wait until the budget allows the call.
+    Queue,
+    // Fail with `Error::RateLimited` at once.
+    Reject,
+}
+
+// Refills at `rate` tokens a second up to `capacity`. Queued calls take their tokens up front, so
+// the balance may go negative and later calls wait behind them.
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_second: u32, burst: u32) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            capacity,
+            rate: per_second.max(1) as f64,
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    // How long until `cost` tokens are there. Costs above the capacity are charged the capacity.
+    fn wait(&self, cost: f64) -> Duration {
+        let deficit = cost.min(self.capacity) - self.tokens;
+        match deficit > 0.0 {
+            true => Duration::from_secs_f64(deficit / self.rate),
+            false => Duration::ZERO,
+        }
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost.min(self.capacity);
+    }
+}
+
+#[derive(Default)]
+struct Budget {
+    requests: Option<TokenBucket>,
+    compute_units: Option<TokenBucket>,
+    // Set from the Retry-After of a rate limited response.
+    paused_until: Option<Instant>,
+}
+
+impl Budget {
+    // Takes the tokens for a call and returns how long it has to wait, or with `reserve` unset,
+    // only returns the wait and takes nothing unless the call can go at once.
+    fn charge(&mut self, requests: f64, compute_units: f64, reserve: bool) -> Duration {
+        let now = Instant::now();
+        let mut wait = self.paused_until.map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        for (bucket, cost) in [(&mut self.requests, requests), (&mut self.compute_units, compute_units)] {
+            if let Some(bucket) = bucket {
+                bucket.refill(now);
+                wait = wait.max(bucket.wait(cost));
+            }
+        }
+
+        if reserve || wait.is_zero() {
+            for (bucket, cost) in [(&mut self.requests, requests), (&mut self.compute_units, compute_units)] {
+                if let Some(bucket) = bucket {
+                    bucket.take(cost);
+                }
+            }
+        }
+        wait
+    }
+
+    fn pause(&mut self, delay: Duration) {
+        let until = Instant::now() + delay;
+        self.paused_until = Some(self.paused_until.map_or(until, |paused| paused.max(until)));
+    }
+}
+
+// Keeps calls to the inner channel within a requests per second budget and a compute unit budget,
+// where each method costs what the cost table says. Wrap each provider in its own channel to give
+// it its own limits. When the provider answers with a Retry-After anyway, calls hold off that long.
+pub struct RateLimitedChannel<C> {
+    inner: C,
+    budget: Mutex<Budget>,
+    costs: HashMap<String, u32>,
+    default_cost: u32,
+    overflow: Overflow,
+}
+
+impl<C> RateLimitedChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            budget: Mutex::new(Budget::default()),
+            costs: HashMap::new(),
+            default_cost: 1,
+            overflow: Overflow::Queue,
+        }
+    }
+
+    pub fn with_request_limit(self, per_second: u32, burst: u32) -> Self {
+        self.budget.lock().requests = Some(TokenBucket::new(per_second, burst));
+        self
+    }
+
+    pub fn with_compute_unit_limit(self, per_second: u32, burst: u32) -> Self {
+        self.budget.lock().compute_units = Some(TokenBucket::new(per_second, burst));
+        self
+    }
+
+    pub fn with_cost<M>(mut self, method: M, compute_units: u32) -> Self
+    where
+        M: Into<String>,
+    {
+        self.costs.insert(method.into(), compute_units);
+        self
+    }
+
+    pub fn with_default_cost(mut self, compute_units: u32) -> Self {
+        self.default_cost = compute_units;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn cost(&self, method: &str) -> u32 {
+        self.costs.get(method).copied().unwrap_or(self.default_cost)
+    }
+
+    async fn admit(&self, requests: usize, compute_units: u32) -> Result<(), Error> {
+        let reserve = self.overflow == Overflow::Queue;
+        let wait = self.budget.lock().charge(requests as f64, compute_units as f64, reserve);
+        if wait.is_zero() {
+            return Ok(());
+        }
+
+        match self.overflow {
+            Overflow::Queue => {
+                tokio::time::sleep(wait).await;
+                Ok(())
+            }
+            Overflow::Reject => Err(Error::RateLimited(wait)),
+        }
+    }
+
+    fn observe<T>(&self, result: &Result<T, Error>) {
+        if let Err(Error::RateLimited(delay)) = result {
+            log::warn!("Rate limited by provider, holding off for {:?}", delay);
+            self.budget.lock().pause(*delay);
+        }
+    }
+}
+
+#[async_trait]
+impl<C> OneshotChannel for RateLimitedChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        self.admit(1, self.cost(&jsonrpc.method)).await?;
+        let result = self.inner.fire(jsonrpc).await;
+        self.observe(&result);
+        result
+    }
+
+    // Providers count each request of a batch, so the batch is charged for all of them.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let compute_units = batch.iter().map(|jsonrpc| self.cost(&jsonrpc.method)).sum();
+        self.admit(batch.len(), compute_units).await?;
+        let result = self.inner.fire_batch(batch).await;
+        self.observe(&result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn call(method: &'static str) -> JsonRpc {
+        JsonRpc::format(Id::Num(1), method, json!(null))
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_queue() {
+        let channel = RateLimitedChannel::new(MockChannel::new())
+            .with_request_limit(20, 2);
+
+        let started = Instant::now();
+        for _ in 0..4 {
+            channel.fire(&call("eth_blockNumber")).await.unwrap();
+        }
+        // Two calls go out at once and the other two wait 50ms each for a token.
+        assert!(started.elapsed() >= Duration::from_millis(90));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_compute_units() {
+        let channel = RateLimitedChannel::new(MockChannel::new())
+            .with_compute_unit_limit(100, 100)
+            .with_cost("eth_getLogs", 75)
+            .with_default_cost(10)
+            .with_overflow(Overflow::Reject);
+
+        channel.fire(&call("eth_getLogs")).await.unwrap();
+        channel.fire(&call("eth_chainId")).await.unwrap();
+        channel.fire(&call("eth_chainId")).await.unwrap();
+        match channel.fire(&call("eth_getLogs")).await {
+            Err(Error::RateLimited(wait)) => assert!(wait > Duration::from_millis(500)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 3);
+
+        let batch = vec![call("eth_chainId"), call("eth_chainId")];
+        assert!(matches!(channel.fire_batch(&batch).await, Err(Error::RateLimited(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_retry_after() {
+        let inner = MockChannel::new().with_outcomes(vec![Err(Error::RateLimited(Duration::from_millis(100)))]);
+        let channel = RateLimitedChannel::new(inner);
+
+        assert!(matches!(channel.fire(&call("eth_chainId")).await, Err(Error::RateLimited(_))));
+
+        let started = Instant::now();
+        channel.fire(&call("eth_chainId")).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(90));
+
+        let channel = channel.with_overflow(Overflow::Reject);
+        channel.budget.lock().pause(Duration::from_millis(100));
+        assert!(matches!(channel.fire(&call("eth_chainId")).await, Err(Error::RateLimited(_))));
+    }
+}
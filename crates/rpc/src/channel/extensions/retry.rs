@@ -0,0 +1,355 @@
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite;
+use crate::channel::{Backoff, OneshotChannel};
+use crate::jsonrpc::{JsonRpc, Response};
+use crate::Error;
+
+// A failed call as seen by a retry policy.
+pub enum Failure<'a> {
+    // The call never produced a response.
+    Error(&'a Error),
+    // The node answered with a JSON-RPC error object.
+    JsonRpc(&'a serde_json::Value),
+}
+
+pub trait RetryPolicy: Send + Sync {
+    fn retryable(&self, failure: &Failure) -> bool;
+}
+
+impl<F> RetryPolicy for F
+where
+    F: Fn(&Failure) -> bool + Send + Sync,
+{
+    fn retryable(&self, failure: &Failure) -> bool {
+        self(failure)
+    }
+}
+
+// Retries dropped connections, connect and timeout failures, HTTP 429 and 5xx, and JSON-RPC errors
+// whose code is listed. Reverts are returned at once whatever their code, since they fail the same
+// way every time, and so is an open circuit, which would only be retried into again before its
+// cool-down is over. A policy that does retry it waits out the remaining cool-down first. Fallback
+// channels move on from an open circuit whatever the policy.
+#[derive(Debug, Clone)]
+pub struct TransientPolicy {
+    codes: Vec<i64>,
+}
+
+impl Default for TransientPolicy {
+    fn default() -> Self {
+        Self {
+            // Limit exceeded and internal error.
+            codes: vec![-32005, -32603],
+        }
+    }
+}
+
+impl TransientPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_code(mut self, code: i64) -> Self {
+        self.codes.push(code);
+        self
+    }
+
+    fn retryable_response(&self, error: &serde_json::Value) -> bool {
+        let message = error.get("message").and_then(|message| message.as_str()).unwrap_or_default();
+        if message.to_lowercase().contains("revert") {
+            return false;
+        }
+
+        error.get("code")
+            .and_then(|code| code.as_i64())
+            .is_some_and(|code| self.codes.contains(&code))
+    }
+}
+
+impl RetryPolicy for TransientPolicy {
+    fn retryable(&self, failure: &Failure) -> bool {
+        match failure {
+            Failure::Error(Error::HttpStatus(status)) => *status == 429 || (500..600).contains(status),
+            Failure::Error(Error::JsonRpcError(error)) => self.retryable_response(error),
+            Failure::Error(Error::WebsocketError(err)) => {
+                matches!(**err, tungstenite::Error::Io(_) | tungstenite::Error::ConnectionClosed)
+            }
+            Failure::Error(Error::UnahandledError(err)) => err.downcast_ref::<reqwest::Error>()
+                .is_some_and(|err| err.is_connect() || err.is_timeout()),
+            Failure::Error(err) => matches!(err,
+                Error::ConnectionError(_)
+                | Error::ConnectionClosed { .. }
+                | Error::HeartbeatTimeout(_)
+                | Error::RateLimited(_)
+                | Error::ResponseDroppedError
+            ),
+            Failure::JsonRpc(error) => self.retryable_response(error),
+        }
+    }
+}
+
+// How long the error says to hold off before calling again.
+fn retry_after(err: &Error) -> Duration {
+    match err {
+        Error::RateLimited(delay) | Error::CircuitOpen(delay) => *delay,
+        _ => Duration::ZERO,
+    }
+}
+
+// Retries failed calls on the inner channel with backoff. The backoff's max attempts bounds the
+// retries after the first call, and the deadline bounds the whole call, waiting included.
+pub struct RetryChannel<C> {
+    inner: C,
+    backoff: Backoff,
+    deadline: Option<Duration>,
+    policy: Box<dyn RetryPolicy>,
+}
+
+impl<C> RetryChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            backoff: Backoff::default().with_max_attempts(3),
+            deadline: None,
+            policy: Box::new(TransientPolicy::default()),
+        }
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_policy<P>(mut self, policy: P) -> Self
+    where
+        P: RetryPolicy + 'static,
+    {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn retryable(&self, result: &Result<Response, Error>) -> bool {
+        match result {
+            Ok(response) => response.error.as_ref()
+                .is_some_and(|error| self.policy.retryable(&Failure::JsonRpc(error))),
+            Err(err) => self.policy.retryable(&Failure::Error(err)),
+        }
+    }
+
+    // Runs `call` against the deadline, if there is one.
+    async fn bounded<T, F>(&self, started: Instant, call: F) -> Result<T, Error>
+    where
+        F: std::future::Future<Output=Result<T, Error>>,
+    {
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => return call.await,
+        };
+
+        let remaining = deadline.saturating_sub(started.elapsed());
+        tokio::time::timeout(remaining, call).await
+            .unwrap_or(Err(Error::DeadlineExceeded(deadline)))
+    }
+
+    // Waits out the backoff for `attempt`, or longer if the failure asked for it, and returns false
+    // when out of attempts or time.
+    async fn pause<T>(&self, started: Instant, attempt: usize, result: &Result<T, Error>) -> bool {
+        if self.backoff.exhausted(attempt) {
+            return false;
+        }
+
+        let delay = match result {
+            Err(err) => self.backoff.delay(attempt).max(retry_after(err)),
+            Ok(_) => self.backoff.delay(attempt),
+        };
+        if self.deadline.is_some_and(|deadline| started.elapsed() + delay >= deadline) {
+            return false;
+        }
+
+        tokio::time::sleep(delay).await;
+        true
+    }
+}
+
+#[async_trait]
+impl<C> OneshotChannel for RetryChannel<C>
+where
+    C: OneshotChannel<Output=Response>,
+{
+    type Output = Response;
+
+    async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            let result = self.bounded(started, self.inner.fire(jsonrpc)).await;
+            if !self.retryable(&result) || !self.pause(started, attempt, &result).await {
+                return result;
+            }
+
+            attempt += 1;
+            log::warn!("Retrying {} (attempt {})", jsonrpc.method, attempt);
+        }
+    }
+
+    // Only a batch that fails as a whole is retried; errors for single requests are returned.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            let result = self.bounded(started, self.inner.fire_batch(batch)).await;
+            let retryable = match &result {
+                Ok(_) => false,
+                Err(err) => self.policy.retryable(&Failure::Error(err)),
+            };
+            if !retryable || !self.pause(started, attempt, &result).await {
+                return result;
+            }
+
+            attempt += 1;
+            log::warn!("Retrying batch of {} (attempt {})", batch.len(), attempt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use super::*;
+    use crate::channel::extensions::mock::MockChannel;
+    use crate::jsonrpc::Id;
+
+    fn response(result: Option<serde_json::Value>, error: Option<serde_json::Value>) -> Response {
+        Response { id: Id::Num(1), result, error }
+    }
+
+    fn rejected(code: i64, message: &str) -> Result<Response, Error> {
+        Ok(response(None, Some(json!({ "code": code, "message": message }))))
+    }
+
+    fn retry<C: OneshotChannel<Output=Response>>(inner: C) -> RetryChannel<C> {
+        let backoff = Backoff::new(Duration::from_millis(1), Duration::from_millis(5)).with_max_attempts(3);
+        RetryChannel::new(inner).with_backoff(backoff)
+    }
+
+    fn jsonrpc() -> JsonRpc {
+        JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null))
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient() {
+        let channel = retry(MockChannel::new().with_outcomes(vec![
+            Err(Error::ConnectionError("ws://node".to_string())),
+            Err(Error::HttpStatus(503)),
+            rejected(-32005, "limit exceeded"),
+        ]));
+
+        let response = channel.fire(&jsonrpc()).await.unwrap();
+        assert_eq!(response.result, Some(json!("0x1")));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_deterministic() {
+        let outcomes = vec![
+            rejected(3, "execution reverted"),
+            rejected(-32603, "execution reverted: insufficient balance"),
+            Err(Error::HttpStatus(400)),
+            rejected(-32601, "method not found"),
+            Err(Error::CircuitOpen(Duration::from_secs(30))),
+        ];
+
+        for outcome in outcomes {
+            let channel = retry(MockChannel::new().with_outcomes(vec![outcome]));
+            let result = channel.fire(&jsonrpc()).await;
+            assert!(result.is_err() || result.unwrap().error.is_some());
+            assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhausted() {
+        let outcomes = (0..10).map(|_| Err(Error::HttpStatus(429))).collect();
+        let channel = retry(MockChannel::new().with_outcomes(outcomes));
+
+        assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::HttpStatus(429))));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn test_retry_deadline() {
+        let channel = retry(MockChannel::new().with_latency(Duration::from_millis(200))).with_deadline(Duration::from_millis(20));
+
+        let result = channel.fire(&jsonrpc()).await;
+        assert!(matches!(result, Err(Error::DeadlineExceeded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after() {
+        let channel = retry(MockChannel::new().with_outcomes(vec![Err(Error::RateLimited(Duration::from_millis(50)))]));
+
+        let started = Instant::now();
+        assert!(channel.fire(&jsonrpc()).await.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // Waiting that long would run past the deadline.
+        let channel = retry(MockChannel::new().with_outcomes(vec![Err(Error::RateLimited(Duration::from_secs(5)))]))
+            .with_deadline(Duration::from_millis(100));
+        assert!(matches!(channel.fire(&jsonrpc()).await, Err(Error::RateLimited(_))));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_unhandled() {
+        let outcomes = vec![
+            Err(Error::UnahandledError("disk full".into())),
+            Err(Error::WebsocketError(Box::new(tungstenite::Error::Utf8))),
+        ];
+
+        for outcome in outcomes {
+            let channel = retry(MockChannel::new().with_outcomes(vec![outcome]));
+            assert!(channel.fire(&jsonrpc()).await.is_err());
+            assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 1);
+        }
+
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let channel = retry(MockChannel::new().with_outcomes(vec![Err(Error::WebsocketError(Box::new(refused.into())))]));
+        assert!(channel.fire(&jsonrpc()).await.is_ok());
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let outcomes = vec![rejected(-32000, "header not found")];
+        let policy = |failure: &Failure| matches!(failure, Failure::JsonRpc(error) if error["code"] == -32000);
+        let channel = retry(MockChannel::new().with_outcomes(outcomes)).with_policy(policy);
+
+        assert!(channel.fire(&jsonrpc()).await.unwrap().error.is_none());
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_circuit_open() {
+        let outcomes = vec![Err(Error::CircuitOpen(Duration::from_millis(50)))];
+        let policy = |failure: &Failure| matches!(failure, Failure::Error(Error::CircuitOpen(_)));
+        let channel = retry(MockChannel::new().with_outcomes(outcomes)).with_policy(policy);
+
+        let started = Instant::now();
+        assert!(channel.fire(&jsonrpc()).await.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(channel.inner().calls.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,343 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::channel::extensions::{CacheKey, CacheStore};
+use crate::Error;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS cache (
+        chain_id INTEGER NOT NULL,
+        method TEXT NOT NULL,
+        params TEXT NOT NULL,
+        result TEXT NOT NULL,
+        stored_at INTEGER NOT NULL,
+        PRIMARY KEY (chain_id, method, params)
+    );
+    CREATE INDEX IF NOT EXISTS cache_stored_at ON cache (stored_at);
+";
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+// Keeps results in an SQLite file, one chain per store, so they outlive the process. Entries past
+// the max age are never returned, and eviction drops them and then the oldest entries until the
+// store is within its entry and byte limits. Eviction runs every `eviction_interval` writes rather
+// than on each one, so the limits can be overshot by that many entries in between. Several stores,
+// even for different chains, can share a file, and clones share the connection.
+//
+// As a CacheStore, the queries run on the blocking thread pool so they don't stall the runtime.
+#[derive(Clone)]
+pub struct SqliteStore {
+    connection: Arc<Mutex<Connection>>,
+    chain_id: u64,
+    max_entries: Option<u64>,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    eviction_interval: u64,
+    writes: Arc<AtomicU64>,
+}
+
+impl SqliteStore {
+    pub fn open<P>(path: P, chain_id: u64) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::with_connection(Connection::open(path)?, chain_id)
+    }
+
+    pub fn in_memory(chain_id: u64) -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?, chain_id)
+    }
+
+    fn with_connection(connection: Connection, chain_id: u64) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            chain_id,
+            max_entries: None,
+            max_bytes: None,
+            max_age: None,
+            eviction_interval: 100,
+            writes: Arc::default(),
+        })
+    }
+
+    pub fn with_max_entries(mut self, max_entries: u64) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_eviction_interval(mut self, writes: u64) -> Self {
+        self.eviction_interval = writes.max(1);
+        self
+    }
+
+    pub fn len(&self) -> Result<u64, Error> {
+        let connection = self.connection.lock();
+        let count = connection.query_row(
+            "SELECT COUNT(*) FROM cache WHERE chain_id = ?1",
+            params![self.chain_id as i64],
+            |row| row.get::<_, i64>(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    // Removes entries past the max age and then the oldest ones until the limits are met.
+    pub fn evict(&self) -> Result<(), Error> {
+        let connection = self.connection.lock();
+        let chain_id = self.chain_id as i64;
+
+        if let Some(max_age) = self.max_age {
+            let oldest = now() - max_age.as_millis() as i64;
+            connection.execute("DELETE FROM cache WHERE chain_id = ?1 AND stored_at < ?2", params![chain_id, oldest])?;
+        }
+
+        if let Some(max_entries) = self.max_entries {
+            connection.execute(
+                "DELETE FROM cache WHERE chain_id = ?1 AND rowid IN (
+                    SELECT rowid FROM cache WHERE chain_id = ?1 ORDER BY stored_at DESC, rowid DESC LIMIT -1 OFFSET ?2
+                )",
+                params![chain_id, max_entries as i64],
+            )?;
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            // Keeps the newest entries whose running total of sizes stays within the limit.
+            connection.execute(
+                "DELETE FROM cache WHERE chain_id = ?1 AND rowid IN (
+                    SELECT rowid FROM (
+                        SELECT rowid, SUM(LENGTH(params) + LENGTH(result))
+                            OVER (ORDER BY stored_at DESC, rowid DESC) AS total
+                        FROM cache WHERE chain_id = ?1
+                    ) WHERE total > ?2
+                )",
+                params![chain_id, max_bytes as i64],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Writes a copy of the whole file, all chains included, to `path`.
+    pub fn export<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_string_lossy().into_owned();
+        self.connection.lock().execute("VACUUM INTO ?1", params![path])?;
+        Ok(())
+    }
+
+    // Copies every entry of an exported file in, replacing entries with the same key. Imported
+    // entries count as stored now, so the max age runs from the import, and the limits are applied
+    // right after. They are copied oldest first, so those are still the first to be evicted.
+    pub fn import<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_string_lossy().into_owned();
+        {
+            let connection = self.connection.lock();
+            connection.execute("ATTACH DATABASE ?1 AS fixture", params![path])?;
+            let copied = connection.execute(
+                "INSERT OR REPLACE INTO cache (chain_id, method, params, result, stored_at)
+                    SELECT chain_id, method, params, result, ?1 FROM fixture.cache ORDER BY stored_at, rowid",
+                params![now()],
+            );
+            connection.execute("DETACH DATABASE fixture", [])?;
+            copied?;
+        }
+        self.evict()
+    }
+
+    fn load(&self, key: &CacheKey) -> Result<Option<serde_json::Value>, Error> {
+        let connection = self.connection.lock();
+        let row = connection.query_row(
+            "SELECT result, stored_at FROM cache WHERE chain_id = ?1 AND method = ?2 AND params = ?3",
+            params![self.chain_id as i64, key.method, key.params],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        ).optional()?;
+
+        let expired = |stored_at: i64| self.max_age.is_some_and(|max_age| stored_at + (max_age.as_millis() as i64) < now());
+        match row {
+            Some((result, stored_at)) if !expired(stored_at) => Ok(Some(serde_json::from_str(&result)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn store(&self, key: CacheKey, value: serde_json::Value) -> Result<(), Error> {
+        self.connection.lock().execute(
+            "INSERT OR REPLACE INTO cache (chain_id, method, params, result, stored_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![self.chain_id as i64, key.method, key.params, value.to_string(), now()],
+        )?;
+
+        let writes = self.writes.fetch_add(1, Ordering::Relaxed) + 1;
+        let limited = self.max_entries.is_some() || self.max_bytes.is_some() || self.max_age.is_some();
+        if limited && writes.is_multiple_of(self.eviction_interval) {
+            self.evict()?;
+        }
+        Ok(())
+    }
+}
+
+// Failing to read or write the file is not worth failing the call over, so it is only logged.
+#[async_trait]
+impl CacheStore for SqliteStore {
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        let (store, owned) = (self.clone(), key.clone());
+        let loaded = tokio::task::spawn_blocking(move || store.load(&owned)).await;
+        loaded.unwrap_or_else(|err| Err(Error::UnahandledError(err.into()))).unwrap_or_else(|err| {
+            log::warn!("Failed to read cached {}: {}", key.method, err);
+            None
+        })
+    }
+
+    async fn put(&self, key: CacheKey, value: serde_json::Value) {
+        let (store, method) = (self.clone(), key.method.clone());
+        let stored = tokio::task::spawn_blocking(move || store.store(key, value)).await;
+        if let Err(err) = stored.unwrap_or_else(|err| Err(Error::UnahandledError(err.into()))) {
+            log::warn!("Failed to cache {}: {}", method, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    // A fresh path in the temp directory, removed again when dropped.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+            Self(std::env::temp_dir().join(format!("rpc-{}-{}-{}.sqlite", name, std::process::id(), nanos)))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn key(params: &str) -> CacheKey {
+        CacheKey { method: "eth_getBlockByNumber".to_string(), params: params.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_persists() {
+        let path = TempPath::new("persists");
+        {
+            let store = SqliteStore::open(&path.0, 1).unwrap();
+            store.put(key("[\"0x1\"]"), json!({ "number": "0x1" })).await;
+        }
+
+        let store = SqliteStore::open(&path.0, 1).unwrap();
+        assert_eq!(store.get(&key("[\"0x1\"]")).await, Some(json!({ "number": "0x1" })));
+        assert_eq!(store.get(&key("[\"0x2\"]")).await, None);
+
+        // Other chains sharing the file don't see the entry.
+        let other = SqliteStore::open(&path.0, 137).unwrap();
+        assert_eq!(other.get(&key("[\"0x1\"]")).await, None);
+        assert!(other.is_empty().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_limits() {
+        let store = SqliteStore::in_memory(1).unwrap().with_max_entries(2).with_eviction_interval(1);
+        for number in 0..4 {
+            store.put(key(&number.to_string()), json!(number)).await;
+        }
+        assert_eq!(store.len().unwrap(), 2);
+        assert_eq!(store.get(&key("1")).await, None);
+        assert_eq!(store.get(&key("3")).await, Some(json!(3)));
+
+        // Each entry takes 2 bytes, so only the newest three fit.
+        let store = SqliteStore::in_memory(1).unwrap().with_max_bytes(6).with_eviction_interval(1);
+        for number in 0..5 {
+            store.put(key(&number.to_string()), json!(number)).await;
+        }
+        assert_eq!(store.len().unwrap(), 3);
+        assert_eq!(store.get(&key("1")).await, None);
+        assert_eq!(store.get(&key("2")).await, Some(json!(2)));
+
+        let store = SqliteStore::in_memory(1).unwrap().with_max_age(Duration::from_millis(20));
+        store.put(key("0"), json!(0)).await;
+        assert_eq!(store.get(&key("0")).await, Some(json!(0)));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.get(&key("0")).await, None);
+        store.evict().unwrap();
+        assert!(store.is_empty().unwrap());
+
+        // Eviction waits for the interval's worth of writes.
+        let store = SqliteStore::in_memory(1).unwrap().with_max_entries(2).with_eviction_interval(3);
+        for number in 0..5 {
+            store.put(key(&number.to_string()), json!(number)).await;
+        }
+        assert_eq!(store.len().unwrap(), 4);
+        store.put(key("5"), json!(5)).await;
+        assert_eq!(store.len().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_export() {
+        let fixture = TempPath::new("fixture");
+        let store = SqliteStore::in_memory(1).unwrap();
+        store.put(key("0"), json!("0xa")).await;
+        store.export(&fixture.0).unwrap();
+
+        let imported = SqliteStore::in_memory(1).unwrap();
+        imported.put(key("0"), json!("stale")).await;
+        imported.import(&fixture.0).unwrap();
+        assert_eq!(imported.get(&key("0")).await, Some(json!("0xa")));
+        assert_eq!(imported.len().unwrap(), 1);
+
+        // Old entries are fresh once imported, and the limits hold straight away.
+        let store = SqliteStore::in_memory(1).unwrap();
+        for number in 1..4 {
+            store.put(key(&number.to_string()), json!(number)).await;
+        }
+        store.connection.lock().execute("UPDATE cache SET stored_at = 0", []).unwrap();
+        let fixture = TempPath::new("old");
+        store.export(&fixture.0).unwrap();
+
+        let imported = SqliteStore::in_memory(1).unwrap()
+            .with_max_age(Duration::from_secs(60))
+            .with_max_entries(2);
+        imported.import(&fixture.0).unwrap();
+        assert_eq!(imported.len().unwrap(), 2);
+        assert_eq!(imported.get(&key("1")).await, None);
+        assert_eq!(imported.get(&key("3")).await, Some(json!(3)));
+
+        // Eviction keeps the entries that were newest in the fixture, whatever order they were
+        // written in.
+        store.connection.lock().execute("UPDATE cache SET stored_at = 10 - rowid", []).unwrap();
+        let fixture = TempPath::new("reordered");
+        store.export(&fixture.0).unwrap();
+
+        let imported = SqliteStore::in_memory(1).unwrap().with_max_entries(2);
+        imported.import(&fixture.0).unwrap();
+        assert_eq!(imported.get(&key("1")).await, Some(json!(1)));
+        assert_eq!(imported.get(&key("2")).await, Some(json!(2)));
+        assert_eq!(imported.get(&key("3")).await, None);
+    }
+}
@@ -1,5 +1,6 @@
+use std::time::Duration;
 use crate::channel::OneshotChannel;
-use crate::jsonrpc::{JsonRpc, Response};
+use crate::jsonrpc::{self, JsonRpc, Response};
 use crate::Error;
 
 pub struct HttpChannel {
@@ -17,18 +18,67 @@ impl HttpChannel {
             endpoint: endpoint.into(),
         }
     }
+
+    // A 429 carrying Retry-After in seconds is reported as rate limiting so callers can wait that
+    // long. Other error statuses are reported as such unless the body is still a JSON-RPC response,
+    // which some providers send along with 4xx and 5xx statuses.
+    async fn post<B, R>(&self, body: &B) -> Result<R, Error>
+    where
+        B: serde::Serialize + ?Sized,
+        for <'de> R: serde::Deserialize<'de>,
+    {
+        let response = self.http.post(&self.endpoint)
+            .json(body).send().await?;
+        let status = response.status();
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let bytes = response.bytes().await?;
+
+        if status.is_success() {
+            return Ok(serde_json::from_slice::<R>(&bytes)?);
+        }
+        if let Some(delay) = retry_after.filter(|_| status == reqwest::StatusCode::TOO_MANY_REQUESTS) {
+            return Err(Error::RateLimited(delay));
+        }
+        match serde_json::from_slice::<serde_json::Value>(&bytes) {
+            Ok(value) if is_response(&value) => Ok(serde_json::from_value::<R>(value)?),
+            _ => Err(Error::HttpStatus(status.as_u16())),
+        }
+    }
+}
+
+// A JSON-RPC response object, or a batch of them.
+fn is_response(value: &serde_json::Value) -> bool {
+    let is_object = |value: &serde_json::Value| value.as_object()
+        .is_some_and(|object| object.contains_key("id") && (object.contains_key("result") || object.contains_key("error")));
+
+    match value {
+        serde_json::Value::Array(values) => !values.is_empty() && values.iter().all(is_object),
+        value => is_object(value),
+    }
 }
 
 #[async_trait]
 impl OneshotChannel for HttpChannel {
     type Output = Response;
     async fn fire(&self, json: &JsonRpc) -> Result<Self::Output, Error> {
-        let response: Response = self.http.post(&self.endpoint)
-            .json(json).send().await?
-            .json().await?;
-
+        let response: Response = self.post(json).await?;
         Ok(response)
     }
+
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        jsonrpc::validate_batch(batch)?;
+
+        let response: serde_json::Value = self.post(batch).await?;
+
+        let responses = jsonrpc::parse_batch(response)?;
+        Ok(jsonrpc::match_batch(batch, responses))
+    }
 }
 
 #[cfg(test)]
@@ -65,7 +115,65 @@ mod tests {
         assert_eq!(result.id, Id::Num(1));
 
         let result = result.as_result::<String>();
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
+
+    }
+
+    #[tokio::test]
+    async fn requests_ethereum_batch() {
+        let docker = Cli::docker();
+        let container = docker.run(ganache());
+
+        let endpoint = format!("http://localhost:{}", container.get_host_port_ipv4(8545));
+
+        let http = HttpChannel::new(endpoint);
+        let batch = vec![
+            JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null)),
+            JsonRpc::format(Id::Num(2), "eth_unknownMethod", json!(null)),
+            JsonRpc::format(Id::Num(3), "eth_chainId", json!(null)),
+        ];
+
+        let results = http.fire_batch(&batch)
+            .await.expect("Failed to send batch");
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().id, Id::Num(1));
+        assert!(results[1].as_ref().unwrap().clone().as_result::<String>().is_err());
+        assert_eq!(results[2].as_ref().unwrap().id, Id::Num(3));
+    }
+
+    #[tokio::test]
+    async fn requests_http_status() {
+        // Answers with an empty 429, then with a JSON-RPC error carried by a 500, then with a 429
+        // asking to retry later, then with error statuses whose JSON bodies are not JSON-RPC.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let body = json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32603, "message": "internal" } }).to_string();
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\ncontent-length: 0\r\n\r\n".to_string(),
+                format!("HTTP/1.1 500 Internal Server Error\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}", body.len(), body),
+                "HTTP/1.1 429 Too Many Requests\r\nretry-after: 2\r\ncontent-length: 0\r\n\r\n".to_string(),
+                "HTTP/1.1 429 Too Many Requests\r\nretry-after: 3\r\ncontent-length: 2\r\n\r\n{}".to_string(),
+                "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 2\r\n\r\n[]".to_string(),
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0; 4096];
+                tokio::io::AsyncReadExt::read(&mut stream, &mut buffer).await.unwrap();
+                tokio::io::AsyncWriteExt::write_all(&mut stream, response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let http = HttpChannel::new(endpoint);
+        let jsonrpc = JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null));
+
+        assert!(matches!(http.fire(&jsonrpc).await, Err(Error::HttpStatus(429))));
+        let response = http.fire(&jsonrpc).await.unwrap();
+        assert_eq!(response.error.unwrap()["code"], -32603);
+        assert!(matches!(http.fire(&jsonrpc).await, Err(Error::RateLimited(delay)) if delay == Duration::from_secs(2)));
 
+        let batch = vec![jsonrpc];
+        assert!(matches!(http.fire_batch(&batch).await, Err(Error::RateLimited(delay)) if delay == Duration::from_secs(3)));
+        assert!(matches!(http.fire_batch(&batch).await, Err(Error::HttpStatus(503))));
     }
 }
@@ -1,11 +1,23 @@
 pub use oneshot::OneshotChannel;
 pub use subscription::{SubscriptionChannel, Subscriber};
+pub(crate) use subscription::subscription_key;
 pub use http::HttpChannel;
 pub use ws::WebsocketChannel;
-pub use extensions::RoundRobinChannel;
+pub use client::{WebsocketClient, ConnectionEvents};
+pub use backoff::Backoff;
+pub use reconnect::{Reconnect, InFlight, ConnectionEvent};
+pub use extensions::{BalancedChannel, ProviderId, RetryChannel, RetryPolicy, TransientPolicy, Failure};
+pub use extensions::{FallbackChannel, QuorumChannel, Threshold, RateLimitedChannel, Overflow};
+pub use extensions::{CachingChannel, CacheStore, CacheKey, CacheRule, LruStore};
+#[cfg(feature = "sqlite")]
+pub use extensions::SqliteStore;
+pub use extensions::{BatchingChannel, HedgedChannel, HedgeDelay, CircuitBreakerChannel, BreakerState};
 
 mod oneshot;
 mod subscription;
 mod http;
 mod ws;
+mod client;
+mod backoff;
+mod reconnect;
 mod extensions;
@@ -1,7 +1,17 @@
 use crate::{JsonRpc, Error};
 
 #[async_trait]
-pub trait OneshotChannel {
-    type Output;
+pub trait OneshotChannel: Send + Sync {
+    type Output: Send;
     async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error>;
+
+    // Results are in request order. Channels that can carry a JSON-RPC batch in one round trip
+    // override this; the default fires the requests one by one.
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        let mut results = Vec::with_capacity(batch.len());
+        for jsonrpc in batch {
+            results.push(self.fire(jsonrpc).await);
+        }
+        Ok(results)
+    }
 }
@@ -0,0 +1,63 @@
+use std::time::Duration;
+use crate::channel::Backoff;
+
+// What happens to requests that were sent but not answered when the connection dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InFlight {
+    // Fail them with a connection error; the caller decides whether to retry.
+    Fail,
+    // Send them again once reconnected. Only safe for idempotent requests.
+    Retry,
+}
+
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    pub backoff: Backoff,
+    pub in_flight: InFlight,
+    // How many times a retried request is sent again before it fails.
+    pub retries: usize,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            backoff: Backoff::default(),
+            in_flight: InFlight::Fail,
+            retries: 3,
+        }
+    }
+}
+
+impl Reconnect {
+    pub fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_in_flight(mut self, in_flight: InFlight) -> Self {
+        self.in_flight = in_flight;
+        self
+    }
+
+    // Retried requests still unanswered after this many resends fail with the error that ended
+    // the last connection.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+// Emitted as a connection drops and recovers. Notifications sent between `Disconnected` and
+// `Resubscribed` are lost, so consumers should backfill whatever they might have missed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    Disconnected,
+    Reconnecting { attempt: usize, delay: Duration },
+    Reconnected { attempts: usize },
+    Resubscribed { previous: String, current: String },
+    ResubscribeFailed { subscription: String, reason: String },
+    // Reconnection gave up or the client was dropped; nothing more will be delivered.
+    Closed,
+}
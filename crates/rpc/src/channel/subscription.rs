@@ -1,5 +1,12 @@
+use futures::channel::oneshot;
+use futures::StreamExt;
+use crate::jsonrpc::Id;
 use crate::{JsonRpc, Error};
 
+// Asks the task behind a subscription to unsubscribe. Dropping it does the same without waiting
+// for the outcome.
+pub(crate) type Cancel = oneshot::Sender<oneshot::Sender<Result<bool, Error>>>;
+
 #[async_trait]
 pub trait SubscriptionChannel {
     type Item;
@@ -10,12 +17,50 @@ pub trait SubscriptionChannel {
 pub struct Subscriber<Item> {
     #[pin]
     stream: Box<dyn futures::Stream<Item=Result<Item, Error>> + Unpin>,
+    id: Option<String>,
+    cancel: Option<Cancel>,
 }
 
 
 impl<Item> Subscriber<Item> {
     pub fn new(stream: Box<dyn futures::Stream<Item=Result<Item, Error>> + Unpin>) -> Self {
-        Self { stream }
+        Self { stream, id: None, cancel: None }
+    }
+
+    pub(crate) fn with_cancel(mut self, id: String, cancel: Cancel) -> Self {
+        self.id = Some(id);
+        self.cancel = Some(cancel);
+        self
+    }
+
+    // The server-side subscription id, when the channel tracks one.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    // Transforms or drops items while keeping the subscription id and cancellation.
+    pub fn filter_map<T, F>(self, mut f: F) -> Subscriber<T>
+    where
+        Item: 'static,
+        T: 'static,
+        F: FnMut(Result<Item, Error>) -> Option<Result<T, Error>> + 'static,
+    {
+        let stream = self.stream.filter_map(move |item| futures::future::ready(f(item)));
+        Subscriber { stream: Box::new(stream), id: self.id, cancel: self.cancel }
+    }
+
+    // Returns whether the server confirmed the unsubscribe.
+    pub async fn unsubscribe(mut self) -> Result<bool, Error> {
+        let cancel = match self.cancel.take() {
+            Some(cancel) => cancel,
+            None => return Ok(false),
+        };
+
+        let (reply, receiver) = oneshot::channel();
+        if cancel.send(reply).is_err() {
+            return Ok(false);
+        }
+        receiver.await.unwrap_or(Ok(false))
     }
 }
 
@@ -27,3 +72,26 @@ impl<Item> futures::Stream for Subscriber<Item> {
         this.stream.poll_next(cx)
     }
 }
+
+// Subscription ids are usually strings but some servers use numbers, which are kept as written.
+pub(crate) fn subscription_key(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(key) => key.clone(),
+        value => value.to_string(),
+    }
+}
+
+// `eth_subscribe` pairs with `eth_unsubscribe`, and likewise for other namespaces.
+pub(crate) fn unsubscribe_request(subscribe: &JsonRpc, id: Id, subscription: &str) -> JsonRpc {
+    let method = match subscribe.method.strip_suffix("subscribe") {
+        Some(namespace) => format!("{}unsubscribe", namespace),
+        None => "eth_unsubscribe".to_string(),
+    };
+
+    JsonRpc {
+        jsonrpc: subscribe.jsonrpc.clone(),
+        method,
+        params: json!([subscription]),
+        id: Some(id),
+    }
+}
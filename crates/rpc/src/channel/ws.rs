@@ -1,25 +1,12 @@
-use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 use futures::lock::Mutex;
-use futures::{SinkExt, StreamExt};
-use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite;
-use tokio_tungstenite::{WebSocketStream, MaybeTlsStream};
 
 use crate::jsonrpc::{JsonRpc, Response};
-use crate::channel::{OneshotChannel, SubscriptionChannel};
+use crate::channel::{ConnectionEvents, OneshotChannel, Reconnect, Subscriber, SubscriptionChannel, WebsocketClient};
+use crate::channel::client::Heartbeat;
 use crate::Error;
 
-use super::Subscriber;
-
-type Session = WebSocketStream<MaybeTlsStream<TcpStream>>;
-
-async fn connection(endpoint: &str) -> Result<Session, Error> {
-    let (stream, _) = tokio_tungstenite::connect_async(endpoint).await?;
-    Ok(stream)
-}
-
 pub struct WebsocketChannel;
 
 impl WebsocketChannel {
@@ -28,8 +15,9 @@ impl WebsocketChannel {
         E: Into<String>,
     {
         WebsocketOneshotChannel {
-            session: Mutex::new(None),
             endpoint: endpoint.into(),
+            reconnect: None,
+            client: Mutex::new(None),
         }
     }
 
@@ -40,122 +28,100 @@ impl WebsocketChannel {
         WebsocketSubscriptionChannel {
             endpoint: endpoint.into(),
             heartbeat,
+            timeout: heartbeat * 2,
+            reconnect: None,
+            client: Mutex::new(Weak::new()),
         }
     }
 }
 
+// Requests share one connection and are matched to their responses by id, so any number of them
+// can be in flight at once. The connection is opened with the first request and again with the
+// next one after it closed.
 pub struct WebsocketOneshotChannel {
-    session: Mutex<Option<Session>>,
     endpoint: String,
+    reconnect: Option<Reconnect>,
+    client: Mutex<Option<Arc<WebsocketClient>>>,
 }
 
+impl WebsocketOneshotChannel {
+    // Reconnects with backoff when the connection drops, sending unanswered requests again over
+    // the new one if the policy retries them. Without a policy they fail with the connection.
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    async fn client(&self) -> Result<Arc<WebsocketClient>, Error> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.as_ref().filter(|client| !client.is_closed()) {
+            return Ok(client.clone());
+        }
+
+        let connected = Arc::new(WebsocketClient::establish(self.endpoint.clone(), self.reconnect.clone(), None).await?);
+        *client = Some(connected.clone());
+        Ok(connected)
+    }
+}
 
 #[async_trait]
 impl OneshotChannel for WebsocketOneshotChannel {
     type Output = Response;
 
-    // TODO: Handle reconnect
     async fn fire(&self, json: &JsonRpc) -> Result<Self::Output, Error> {
-        let mut session_lock = self.session.lock().await;
-        let session = match session_lock.as_mut() {
-            Some(session) => session,
-            None => {
-                log::info!("Connecting to {}", self.endpoint.as_str());
-                *session_lock = Some(connection(self.endpoint.as_str()).await?);
-                match session_lock.as_mut() {
-                    Some(session) => session,
-                    _ => Err(Error::ConnectionError(self.endpoint.clone()))?
-                }
-            }
-        };
-
-        let message = match serde_json::to_string(json) {
-            Ok(message) => message,
-            Err(err) => Err(Error::JsonformatError(err.into()))?
-        };
-
-        let send_message = tungstenite::Message::Text(message);
-        session.send(send_message).await?;
-
-        let response = session.next().await.ok_or(Error::ConnectionError(self.endpoint.clone()))?;
-        let recv_message = response?;
-
-        let response = match recv_message {
-            tungstenite::Message::Text(text) => {
-                match serde_json::from_str::<Response>(&text) {
-                    Ok(response) => response,
-                    Err(err) => Err(Error::JsonformatError(err.into()))?
-                }
-            },
-            _ => Err(Error::ConnectionError(self.endpoint.clone()))?
-        };
+        self.client().await?.fire(json).await
+    }
 
-        Ok(response)
+    async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+        self.client().await?.fire_batch(batch).await
     }
 }
 
+// Subscriptions share one connection, opened with the first of them and closed with the last.
+// The server is pinged every heartbeat.
 pub struct WebsocketSubscriptionChannel {
     endpoint: String,
     heartbeat: Duration,
+    timeout: Duration,
+    reconnect: Option<Reconnect>,
+    // Held by the subscribers, so it is dropped along with the last of them.
+    client: Mutex<Weak<WebsocketClient>>,
 }
 
 impl WebsocketSubscriptionChannel {
-    async fn connection(&self) -> Result<Session, Error> {
-        log::info!("Connecting to {}", self.endpoint.as_str());
-        let (stream, _) = tokio_tungstenite::connect_async(self.endpoint.clone()).await?;
-        Ok(stream)
+    // How long the server may stay silent, pongs included, before the subscriptions fail with
+    // a heartbeat timeout. Defaults to two heartbeats.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
-}
 
-#[pin_project]
-pub struct WebsocketSubscription {
-    #[pin]
-    stream: futures::stream::SplitStream<Session>,
-}
-
-impl WebsocketSubscription {
-    fn new(stream: futures::stream::SplitStream<Session>) -> Self {
-        Self { stream }
+    // Reconnects with backoff when the connection drops or goes silent, and re-establishes the
+    // active subscriptions on the new one. Without a policy they end with the connection.
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
     }
-}
 
-impl futures::Stream for WebsocketSubscription {
-    type Item = Result<JsonRpc, Error>;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        loop {
-            let this = self.as_mut().project();
-            match this.stream.poll_next(cx) {
-                Poll::Pending => return Poll::Pending,
-                Poll::Ready(None) => return Poll::Ready(None),
-                Poll::Ready(Some(Ok(tungstenite::Message::Text(text)))) => {
-                    match serde_json::from_str::<JsonRpc>(&text) {
-                        Ok(response) => return Poll::Ready(Some(Ok(response))),
-                        Err(err) => {
-                            log::error!("Failed to parse response: {}", err);
-                            return Err(Error::JsonformatError(err.into()))?
-                        }
-                    }
-                },
-                Poll::Ready(Some(Ok(tungstenite::Message::Binary(bytes)))) => {
-                    match serde_json::from_slice::<JsonRpc>(bytes.as_ref()) {
-                        Ok(response) => return Poll::Ready(Some(Ok(response))),
-                        Err(err) => {
-                            log::error!("Failed to parse response: {}", err);
-                            return Err(Error::JsonformatError(err.into()))?
-                        }
-                    }
-                },
-                Poll::Ready(Some(Ok(tungstenite::Message::Pong(_)))) => {
-                    log::debug!("Received Pong");
-                    continue
-                }
-                rest => {
-                    unimplemented!("WebsocketSubscription received unsupported messsage: {:?}", rest)
-                }
-            }
+    // Events of the connection the active subscriptions share. Without any there is none to
+    // report on.
+    pub async fn events(&self) -> Result<ConnectionEvents, Error> {
+        match self.client.lock().await.upgrade() {
+            Some(client) => Ok(client.events()),
+            None => Err(Error::ConnectionError(self.endpoint.clone())),
+        }
+    }
 
+    async fn client(&self) -> Result<Arc<WebsocketClient>, Error> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.upgrade().filter(|client| !client.is_closed()) {
+            return Ok(client);
         }
+
+        let heartbeat = Heartbeat { interval: self.heartbeat, timeout: self.timeout };
+        let connected = Arc::new(WebsocketClient::establish(self.endpoint.clone(), self.reconnect.clone(), Some(heartbeat)).await?);
+        *client = Arc::downgrade(&connected);
+        Ok(connected)
     }
 }
 
@@ -164,38 +130,29 @@ impl SubscriptionChannel for WebsocketSubscriptionChannel {
     type Item = JsonRpc;
 
     async fn subscribe(&self, jsonrpc: &JsonRpc) -> Result<Subscriber<Self::Item>, Error> {
-        let session = self.connection().await?;
-        let (mut writer, mut reader) = session.split();
-
-        let message = match serde_json::to_string(jsonrpc) {
-            Ok(message) => message,
-            Err(err) => Err(Error::JsonformatError(err.into()))?
-        };
-
-        let send_message = tungstenite::Message::Text(message);
-        writer.send(send_message).await?;
-        let _response = reader.next().await.ok_or(Error::ResponseDroppedError)?;
-
-        let heartbeat = self.heartbeat;
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(heartbeat).await;
-                let message = tungstenite::Message::Ping(Vec::new());
-                writer.send(message).await.ok();
-            }
-        });
-
-        let websocket_subscription = WebsocketSubscription::new(reader);
-        let subscriber = Subscriber::new(Box::new(websocket_subscription));
-        Ok(subscriber)
+        let client = self.client().await?;
+        let subscriber = client.subscribe(jsonrpc).await?;
+
+        // Subscribers keep the connection open even once the channel is gone.
+        Ok(subscriber.filter_map(move |item| {
+            let _ = &client;
+            Some(item)
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use futures::channel::mpsc;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite;
+    use tokio_tungstenite::{WebSocketStream, MaybeTlsStream};
     use super::*;
     use crate::jsonrpc::{JsonRpc, Id};
-    use crate::channel::OneshotChannel;
+    use crate::channel::{ConnectionEvent, InFlight, OneshotChannel};
+
+    type Session = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
     use testcontainers::images::generic::GenericImage;
     use testcontainers::clients::Cli;
@@ -224,4 +181,305 @@ mod tests {
         let response = channel.fire(&jsonrpc).await.expect("Failed to send request");
         assert_eq!(response.id, Id::Num(1));
     }
+
+    #[tokio::test]
+    async fn test_websocket_batch() {
+        // Answers a batch in reverse order, echoing each method as its result.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(tungstenite::Message::Text(text))) = session.next().await {
+                let batch = serde_json::from_str::<Vec<JsonRpc>>(&text).unwrap();
+                let responses = batch.iter().rev()
+                    .map(|jsonrpc| json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": jsonrpc.method }))
+                    .collect::<Vec<_>>();
+                session.send(tungstenite::Message::Text(json!(responses).to_string())).await.unwrap();
+            }
+        });
+
+        let channel = WebsocketChannel::oneshot(endpoint);
+        let batch = vec![
+            JsonRpc::format(Id::Num(1), "eth_chainId", json!(null)),
+            JsonRpc::format(Id::Str("two".to_string()), "eth_blockNumber", json!(null)),
+        ];
+
+        let results = channel.fire_batch(&batch).await.expect("Failed to send batch");
+        let methods = results.into_iter()
+            .map(|result| result.unwrap().as_result::<String>().unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(methods, vec!["eth_chainId", "eth_blockNumber"]);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_concurrent() {
+        // Waits for two requests, then answers them in reverse order after a stray notification.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut requests = Vec::new();
+            while requests.len() < 2 {
+                if let Some(Ok(tungstenite::Message::Text(text))) = session.next().await {
+                    requests.push(serde_json::from_str::<JsonRpc>(&text).unwrap());
+                }
+            }
+
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xabc", "result": {} },
+            });
+            session.send(tungstenite::Message::Text(notification.to_string())).await.unwrap();
+            for jsonrpc in requests.iter().rev() {
+                let response = json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": jsonrpc.method });
+                session.send(tungstenite::Message::Text(response.to_string())).await.unwrap();
+            }
+            while session.next().await.is_some() {}
+        });
+
+        let channel = WebsocketChannel::oneshot(endpoint);
+        let first = JsonRpc::format(Id::Num(1), "eth_chainId", json!(null));
+        let second = JsonRpc::format(Id::Num(2), "eth_blockNumber", json!(null));
+
+        let (first, second) = futures::future::join(channel.fire(&first), channel.fire(&second)).await;
+        let first = first.unwrap();
+        assert_eq!(first.id, Id::Num(1));
+        assert_eq!(first.as_result::<String>().unwrap().unwrap(), "eth_chainId");
+        let second = second.unwrap();
+        assert_eq!(second.id, Id::Num(2));
+        assert_eq!(second.as_result::<String>().unwrap().unwrap(), "eth_blockNumber");
+    }
+
+    #[tokio::test]
+    async fn test_websocket_reconnect() {
+        // Every connection drops its first request unanswered and answers the rest.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                tokio::spawn(async move {
+                    session.next().await;
+                    session.close(None).await.ok();
+                });
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                tokio::spawn(async move {
+                    while let Some(Ok(tungstenite::Message::Text(text))) = session.next().await {
+                        let jsonrpc = serde_json::from_str::<JsonRpc>(&text).unwrap();
+                        let response = json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": "0x1" });
+                        session.send(tungstenite::Message::Text(response.to_string())).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        let jsonrpc = JsonRpc::format(Id::Num(1), "eth_blockNumber", json!(null));
+
+        // Without a policy the failed request surfaces, but the next one reconnects.
+        let channel = WebsocketChannel::oneshot(endpoint.clone());
+        assert!(channel.fire(&jsonrpc).await.is_err());
+        assert_eq!(channel.fire(&jsonrpc).await.unwrap().id, Id::Num(1));
+
+        let backoff = crate::channel::Backoff::new(Duration::from_millis(1), Duration::from_millis(10));
+        let reconnect = Reconnect::new(backoff).with_in_flight(InFlight::Retry);
+        let channel = WebsocketChannel::oneshot(endpoint).with_reconnect(reconnect);
+        assert_eq!(channel.fire(&jsonrpc).await.unwrap().id, Id::Num(1));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_unsubscribe() {
+        // Hands out a new subscription on every subscribe and reports each connection, unsubscribe
+        // and close.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        let (report, mut reports) = mpsc::unbounded();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+                report.unbounded_send(json!("connected")).unwrap();
+
+                let report = report.clone();
+                tokio::spawn(async move {
+                    let mut subscriptions = ["0xabc", "0xdef"].into_iter();
+                    while let Some(Ok(message)) = session.next().await {
+                        let text = match message {
+                            tungstenite::Message::Text(text) => text,
+                            tungstenite::Message::Close(_) => break,
+                            _ => continue,
+                        };
+                        let jsonrpc = serde_json::from_str::<JsonRpc>(&text).unwrap();
+                        let result = match jsonrpc.method.as_str() {
+                            "eth_subscribe" => json!(subscriptions.next()),
+                            _ => {
+                                report.unbounded_send(jsonrpc.params[0].clone()).unwrap();
+                                json!(true)
+                            }
+                        };
+                        let response = json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": result });
+                        session.send(tungstenite::Message::Text(response.to_string())).await.unwrap();
+                    }
+                    report.unbounded_send(json!("closed")).unwrap();
+                });
+            }
+        });
+
+        let channel = WebsocketChannel::subscription(endpoint, Duration::from_secs(30));
+        let subscribe = JsonRpc::format(Id::Num(1), "eth_subscribe", json!(["newHeads"]));
+
+        let subscriber = channel.subscribe(&subscribe).await.unwrap();
+        assert_eq!(subscriber.id(), Some("0xabc"));
+        assert_eq!(reports.next().await.unwrap(), json!("connected"));
+
+        // The second subscription goes over the same connection.
+        let other = channel.subscribe(&subscribe).await.unwrap();
+        assert_eq!(other.id(), Some("0xdef"));
+
+        assert!(subscriber.unsubscribe().await.unwrap());
+        assert_eq!(reports.next().await.unwrap(), json!("0xabc"));
+
+        // The connection closes with the last subscription, and the next one opens another.
+        assert!(channel.events().await.is_ok());
+        drop(other);
+        assert_eq!(reports.next().await.unwrap(), json!("closed"));
+        assert!(channel.events().await.is_err());
+
+        let subscriber = channel.subscribe(&subscribe).await.unwrap();
+        assert_eq!(subscriber.id(), Some("0xabc"));
+        assert_eq!(reports.next().await.unwrap(), json!("connected"));
+    }
+
+    async fn subscription_server() -> (tokio::net::TcpListener, String) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+        (listener, endpoint)
+    }
+
+    async fn accept_subscription(listener: &tokio::net::TcpListener) -> Session {
+        let (stream, _) = listener.accept().await.unwrap();
+        let stream = MaybeTlsStream::Plain(stream);
+        let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+        let text = session.next().await.unwrap().unwrap().into_text().unwrap();
+        let jsonrpc = serde_json::from_str::<JsonRpc>(&text).unwrap();
+        let response = json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": "0xabc" });
+        session.send(tungstenite::Message::Text(response.to_string())).await.unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_websocket_control_frames() {
+        let (listener, endpoint) = subscription_server().await;
+        let (pong, mut pongs) = mpsc::unbounded();
+        tokio::spawn(async move {
+            let mut session = accept_subscription(&listener).await;
+
+            session.send(tungstenite::Message::Ping(b"ping".to_vec())).await.unwrap();
+            while let Some(Ok(message)) = session.next().await {
+                if let tungstenite::Message::Pong(payload) = message {
+                    pong.unbounded_send(payload).unwrap();
+                    break;
+                }
+            }
+
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xabc", "result": {} },
+            });
+            session.send(tungstenite::Message::Text(notification.to_string())).await.unwrap();
+            session.close(Some(tungstenite::protocol::CloseFrame {
+                code: tungstenite::protocol::frame::coding::CloseCode::Away,
+                reason: "going away".into(),
+            })).await.unwrap();
+            while session.next().await.is_some() {}
+        });
+
+        let channel = WebsocketChannel::subscription(endpoint, Duration::from_secs(30));
+        let subscribe = JsonRpc::format(Id::Num(1), "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = channel.subscribe(&subscribe).await.unwrap();
+
+        assert_eq!(pongs.next().await.unwrap(), b"ping".to_vec());
+        assert_eq!(subscriber.next().await.unwrap().unwrap().method, "eth_subscription");
+        match subscriber.next().await {
+            Some(Err(Error::ConnectionClosed { code, reason })) => {
+                assert_eq!(code, 1001);
+                assert_eq!(reason, "going away");
+            }
+            other => panic!("unexpected {:?}", other.map(|item| item.map(|jsonrpc| jsonrpc.method))),
+        }
+        assert!(subscriber.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_heartbeat_timeout() {
+        let (listener, endpoint) = subscription_server().await;
+        tokio::spawn(async move {
+            // Never reads again, so pings go unanswered.
+            let _session = accept_subscription(&listener).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let channel = WebsocketChannel::subscription(endpoint, Duration::from_millis(20));
+        let subscribe = JsonRpc::format(Id::Num(1), "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = channel.subscribe(&subscribe).await.unwrap();
+
+        match subscriber.next().await {
+            Some(Err(Error::HeartbeatTimeout(silence))) => assert!(silence >= Duration::from_millis(40)),
+            other => panic!("unexpected {:?}", other.map(|item| item.map(|jsonrpc| jsonrpc.method))),
+        }
+        assert!(subscriber.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_resubscribe() {
+        let (listener, endpoint) = subscription_server().await;
+        tokio::spawn(async move {
+            // The first connection stops answering pings, the second resubscribes under a new id.
+            let session = accept_subscription(&listener).await;
+            tokio::spawn(async move {
+                let _session = session;
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            });
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut session = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let text = session.next().await.unwrap().unwrap().into_text().unwrap();
+            let jsonrpc = serde_json::from_str::<JsonRpc>(&text).unwrap();
+            assert_eq!(jsonrpc.method, "eth_subscribe");
+
+            let response = json!({ "jsonrpc": "2.0", "id": jsonrpc.id, "result": "0xdef" });
+            session.send(tungstenite::Message::Text(response.to_string())).await.unwrap();
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xdef", "result": {} },
+            });
+            session.send(tungstenite::Message::Text(notification.to_string())).await.unwrap();
+            while session.next().await.is_some() {}
+        });
+
+        let backoff = crate::channel::Backoff::new(Duration::from_millis(1), Duration::from_millis(10));
+        let channel = WebsocketChannel::subscription(endpoint, Duration::from_millis(20))
+            .with_reconnect(Reconnect::new(backoff));
+        let subscribe = JsonRpc::format(Id::Num(1), "eth_subscribe", json!(["newHeads"]));
+        let mut subscriber = channel.subscribe(&subscribe).await.unwrap();
+        let mut events = channel.events().await.unwrap();
+
+        let notification = subscriber.next().await.unwrap().unwrap();
+        assert_eq!(notification.params["subscription"], "0xdef");
+
+        assert_eq!(events.next().await.unwrap(), ConnectionEvent::Disconnected);
+        assert!(matches!(events.next().await.unwrap(), ConnectionEvent::Reconnecting { attempt: 1, .. }));
+        assert_eq!(events.next().await.unwrap(), ConnectionEvent::Reconnected { attempts: 1 });
+        assert_eq!(
+            events.next().await.unwrap(),
+            ConnectionEvent::Resubscribed { previous: "0xabc".to_string(), current: "0xdef".to_string() },
+        );
+    }
 }
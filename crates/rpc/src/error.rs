@@ -1,4 +1,7 @@
+use std::time::Duration;
 use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use crate::jsonrpc::Id;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -9,10 +12,10 @@ pub enum Error {
     JsonRpcError(serde_json::Value),
 
     #[error("Unhandled error: {0}")]
-    UnahandledError(Box<dyn std::error::Error>),
+    UnahandledError(Box<dyn std::error::Error + Send + Sync>),
 
     #[error("Websocket error: {0}")]
-    WebsocketError(#[from] tungstenite::Error),
+    WebsocketError(Box<tungstenite::Error>),
 
     #[error("Json format error: {0}")]
     JsonformatError(#[from] serde_json::Error),
@@ -20,17 +23,93 @@ pub enum Error {
     #[error("Connection error: {0}")]
     ConnectionError(String),
 
+    #[error("Connection closed with code {code}: {reason}")]
+    ConnectionClosed { code: u16, reason: String },
+
+    #[error("No heartbeat received for {0:?}")]
+    HeartbeatTimeout(Duration),
+
+    #[error("Http status {0}")]
+    HttpStatus(u16),
+
+    #[error("Rate limited, retry after {0:?}")]
+    RateLimited(Duration),
+
+    #[error("Deadline of {0:?} exceeded")]
+    DeadlineExceeded(Duration),
+
+    #[error("Circuit open, retry after {0:?}")]
+    CircuitOpen(Duration),
+
+    #[error("No quorum among {agreeing:?}, disagreeing: {disagreeing:?}")]
+    NoQuorum { agreeing: Vec<String>, disagreeing: Vec<String> },
+
     #[error("Subscription error")]
     ResponseDroppedError,
 
     #[error("Subscription channel not provided")]
     SubscriptionChannelNotProvidedError,
+
+    #[error("No subscription id in the subscribe response")]
+    MissingSubscriptionId,
+
+    #[error("Batch error: {0}")]
+    BatchError(String),
+
+    #[error("Missing response for id {0:?}")]
+    MissingResponse(Id),
+}
+
+impl Error {
+    // A close frame without a status is reported as 1005, as RFC 6455 specifies.
+    pub(crate) fn closed(frame: Option<CloseFrame>) -> Self {
+        match frame {
+            Some(frame) => Error::ConnectionClosed { code: frame.code.into(), reason: frame.reason.into_owned() },
+            None => Error::ConnectionClosed { code: 1005, reason: String::new() },
+        }
+    }
+
+    // A copy for handing one failure to several callers. Errors wrapping a source that can't be
+    // cloned keep only its message.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Error::HexDecodeError(message) => Error::HexDecodeError(message.clone()),
+            Error::JsonRpcError(error) => Error::JsonRpcError(error.clone()),
+            Error::ConnectionError(message) => Error::ConnectionError(message.clone()),
+            Error::ConnectionClosed { code, reason } => Error::ConnectionClosed { code: *code, reason: reason.clone() },
+            Error::HeartbeatTimeout(timeout) => Error::HeartbeatTimeout(*timeout),
+            Error::HttpStatus(status) => Error::HttpStatus(*status),
+            Error::RateLimited(delay) => Error::RateLimited(*delay),
+            Error::DeadlineExceeded(deadline) => Error::DeadlineExceeded(*deadline),
+            Error::CircuitOpen(remaining) => Error::CircuitOpen(*remaining),
+            Error::NoQuorum { agreeing, disagreeing } => Error::NoQuorum { agreeing: agreeing.clone(), disagreeing: disagreeing.clone() },
+            Error::ResponseDroppedError => Error::ResponseDroppedError,
+            Error::SubscriptionChannelNotProvidedError => Error::SubscriptionChannelNotProvidedError,
+            Error::MissingSubscriptionId => Error::MissingSubscriptionId,
+            Error::BatchError(message) => Error::BatchError(message.clone()),
+            Error::MissingResponse(id) => Error::MissingResponse(id.clone()),
+            err @ (Error::UnahandledError(_) | Error::WebsocketError(_) | Error::JsonformatError(_)) => {
+                Error::UnahandledError(err.to_string().into())
+            }
+        }
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        Error::WebsocketError(Box::new(err))
+    }
 }
 
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        match err {
-            _ => Error::UnahandledError(err.into())
-        }
+        Error::UnahandledError(err.into())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::UnahandledError(err.into())
     }
 }
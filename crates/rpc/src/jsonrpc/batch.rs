@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use crate::Error;
+use crate::jsonrpc::{Id, JsonRpc, Response};
+
+// Every request of a batch needs a distinct id, otherwise responses can not be matched back.
+pub fn validate_batch(batch: &[JsonRpc]) -> Result<(), Error> {
+    let mut seen = HashMap::with_capacity(batch.len());
+    for jsonrpc in batch {
+        let id = jsonrpc.id.as_ref()
+            .ok_or_else(|| Error::BatchError(format!("request {} has no id", jsonrpc.method)))?;
+        if seen.insert(id, ()).is_some() {
+            return Err(Error::BatchError(format!("duplicated id {:?}", id)));
+        }
+    }
+    Ok(())
+}
+
+// Servers answer a batch with an array, or with a single error object when the batch itself
+// is rejected. Entries that can not be read as a response (e.g. errors with a null id) are skipped
+// and surface as missing responses.
+pub fn parse_batch(value: serde_json::Value) -> Result<Vec<Response>, Error> {
+    match value {
+        serde_json::Value::Array(items) => Ok(items.into_iter()
+            .filter_map(|item| serde_json::from_value::<Response>(item).ok())
+            .collect()),
+        serde_json::Value::Object(mut object) => match object.remove("error") {
+            Some(error) => Err(Error::JsonRpcError(error)),
+            None => Err(Error::BatchError("expected an array of responses".to_string())),
+        },
+        _ => Err(Error::BatchError("expected an array of responses".to_string())),
+    }
+}
+
+// Orders responses like the requests they answer.
+pub fn match_batch(batch: &[JsonRpc], responses: Vec<Response>) -> Vec<Result<Response, Error>> {
+    let mut responses = responses.into_iter()
+        .map(|response| (response.id.clone(), response))
+        .collect::<HashMap<Id, Response>>();
+
+    batch.iter()
+        .map(|jsonrpc| {
+            let id = jsonrpc.id.clone().expect("validated batch");
+            responses.remove(&id).ok_or(Error::MissingResponse(id))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch() -> Vec<JsonRpc> {
+        vec![
+            JsonRpc::format(1_u32, "eth_chainId", json!(null)),
+            JsonRpc::format(2_u32, "eth_blockNumber", json!(null)),
+            JsonRpc::format(3_u32, "eth_gasPrice", json!(null)),
+        ]
+    }
+
+    #[test]
+    fn test_match_batch() {
+        let responses = parse_batch(json!([
+            { "jsonrpc": "2.0", "id": 3, "result": "0x3" },
+            { "jsonrpc": "2.0", "id": null, "error": { "code": -32600, "message": "Invalid Request" } },
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "Method not found" } },
+        ])).unwrap();
+
+        let results = match_batch(&batch(), responses);
+        assert!(matches!(results[0].as_ref().unwrap().clone().as_result::<String>(), Err(Error::JsonRpcError(_))));
+        assert!(matches!(&results[1], Err(Error::MissingResponse(Id::Num(2)))));
+        assert_eq!(results[2].as_ref().unwrap().id, Id::Num(3));
+    }
+
+    #[test]
+    fn test_rejected_batch() {
+        let rejected = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": "Parse error" } });
+        assert!(matches!(parse_batch(rejected), Err(Error::JsonRpcError(_))));
+    }
+
+    #[test]
+    fn test_validate_batch() {
+        assert!(validate_batch(&batch()).is_ok());
+
+        let mut duplicated = batch();
+        duplicated.push(JsonRpc::format(1_u32, "eth_chainId", json!(null)));
+        assert!(matches!(validate_batch(&duplicated), Err(Error::BatchError(_))));
+
+        let mut notification = batch();
+        notification[0].id = None;
+        assert!(matches!(validate_batch(&notification), Err(Error::BatchError(_))));
+    }
+}
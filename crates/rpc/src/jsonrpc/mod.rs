@@ -1,12 +1,17 @@
+pub use batch::{match_batch, parse_batch, validate_batch};
 pub use id::Id;
 pub use tag::Tag;
 pub use jsonrpc::JsonRpc;
 pub use response::Response;
+pub(crate) use value::normalize;
 
+pub mod batch;
 pub mod id;
 pub mod tag;
+#[allow(clippy::module_inception)]
 pub mod jsonrpc;
 pub mod response;
+mod value;
 
 #[cfg(test)]
 mod tests;
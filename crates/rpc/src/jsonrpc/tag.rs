@@ -26,3 +26,23 @@ where
     let s = s.trim_start_matches("0x");
     u64::from_str_radix(s, 16).map_err(serde::de::Error::custom)
 }
+
+impl Tag {
+    // Reads a block parameter as sent on the wire: a tag name, a hex block number or an EIP-1898
+    // object holding `blockNumber`. Tags this enum doesn't know, such as `safe` and `finalized`, and
+    // block hashes, bare or as `blockHash`, give None.
+    pub fn from_param(param: &serde_json::Value) -> Option<Self> {
+        if let Some(number) = param.get("blockNumber") {
+            return Self::from_param(number);
+        }
+        if !param.is_string() {
+            return None;
+        }
+        if let Ok(tag) = serde_json::from_value::<Tag>(param.clone()) {
+            return Some(tag);
+        }
+
+        let hex = param.as_str()?.strip_prefix("0x")?;
+        u64::from_str_radix(hex, 16).ok().map(Tag::Block)
+    }
+}
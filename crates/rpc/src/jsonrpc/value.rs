@@ -0,0 +1,12 @@
+// Hex strings are compared case-insensitively; everything else as is.
+pub(crate) fn normalize(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(text) if text.starts_with("0x") => serde_json::Value::String(text.to_lowercase()),
+        serde_json::Value::Array(values) => values.iter().map(normalize).collect(),
+        serde_json::Value::Object(fields) => fields.iter()
+            .map(|(key, value)| (key.clone(), normalize(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        value => value.clone(),
+    }
+}
@@ -8,7 +8,10 @@ extern crate num_traits;
 extern crate parking_lot;
 #[macro_use]
 extern crate pin_project;
+extern crate rand;
 extern crate reqwest;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
@@ -7,10 +7,12 @@ use crate::channel::OneshotChannel;
 use crate::channel::{Subscriber, SubscriptionChannel};
 use crate::jsonrpc::{self, JsonRpc, Tag};
 use crate::network::NetworkOptions;
+use crate::network::types::{Header, Log, LogFilter, PendingTransaction};
 
 pub struct EthereumNetwork {
     sequence: Cell<u64>,
     options: NetworkOptions,
+    max_batch: usize,
 }
 
 impl EthereumNetwork {
@@ -18,9 +20,16 @@ impl EthereumNetwork {
         Self {
             sequence: Cell::new(0),
             options,
+            max_batch: 100,
         }
     }
 
+    // Largest number of requests sent in one JSON-RPC batch; bigger batches are split.
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
     fn advance(&self) -> u64 {
         let next_value = self.sequence.get().wrapping_add(1);
         self.sequence.replace(next_value)
@@ -62,6 +71,23 @@ impl EthereumNetwork {
         expect_bigint_response(jsonrpc, channel, self.options.radix).await
     }
 
+    // Fetches many balances in JSON-RPC batches of at most `max_batch`; each address gets its own result.
+    pub async fn balances(&self, channel: &dyn OneshotChannel<Output=jsonrpc::Response>, addresses: &[&str], tag: Tag) -> Result<Vec<Result<Option<BigInt>, Error>>, Error> {
+        let mut balances = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(self.max_batch) {
+            let batch = chunk.iter()
+                .map(|address| JsonRpc::format(self.advance(), "eth_getBalance", json!([address, tag])))
+                .collect::<Vec<_>>();
+
+            let responses = channel.fire_batch(&batch).await?;
+            balances.extend(responses.into_iter().map(|response| {
+                let result = response?.as_result::<String>()?;
+                result.map(|result| bigint_from_hex(result, self.options.radix)).transpose()
+            }));
+        }
+        Ok(balances)
+    }
+
     pub async fn transaction_count(&self, channel: &dyn OneshotChannel<Output=jsonrpc::Response>, address: &str, tag: Tag) -> Result<Option<BigInt>, Error> {
         let params = json!([address,  tag]);
         let jsonrpc = JsonRpc::format(self.advance(), "eth_getTransactionCount", params);
@@ -92,9 +118,28 @@ impl EthereumNetwork {
     pub async fn subscribe(&self, channel: &dyn SubscriptionChannel<Item=JsonRpc>, topic: &str) -> Result<Subscriber<JsonRpc>, Error> {
         let params = json!([topic]);
         let jsonrpc = JsonRpc::format(self.advance(), "eth_subscribe", params);
-        let subscriber = channel.subscribe(&jsonrpc).await.unwrap();
+        let subscriber = channel.subscribe(&jsonrpc).await?;
         Ok(subscriber)
     }
+
+    pub async fn subscribe_new_heads(&self, channel: &dyn SubscriptionChannel<Item=JsonRpc>) -> Result<Subscriber<Header>, Error> {
+        let jsonrpc = JsonRpc::format(self.advance(), "eth_subscribe", json!(["newHeads"]));
+        expect_notifications::<Header>(jsonrpc, channel).await
+    }
+
+    pub async fn subscribe_logs(&self, channel: &dyn SubscriptionChannel<Item=JsonRpc>, filter: &LogFilter) -> Result<Subscriber<Log>, Error> {
+        let jsonrpc = JsonRpc::format(self.advance(), "eth_subscribe", json!(["logs", filter]));
+        expect_notifications::<Log>(jsonrpc, channel).await
+    }
+
+    pub async fn subscribe_pending_transactions(&self, channel: &dyn SubscriptionChannel<Item=JsonRpc>, full: bool) -> Result<Subscriber<PendingTransaction>, Error> {
+        let params = match full {
+            true => json!(["newPendingTransactions", true]),
+            false => json!(["newPendingTransactions"]),
+        };
+        let jsonrpc = JsonRpc::format(self.advance(), "eth_subscribe", params);
+        expect_notifications::<PendingTransaction>(jsonrpc, channel).await
+    }
 }
 
 async fn expect_bigint_response(jsonrpc: JsonRpc, channel: &dyn channel::OneshotChannel<Output=jsonrpc::Response>, radix: u32) -> Result<Option<BigInt>, Error> {
@@ -106,7 +151,7 @@ async fn expect_bigint_response(jsonrpc: JsonRpc, channel: &dyn channel::Oneshot
 async fn expect_bytes_response(jsonrpc: JsonRpc, channel: &dyn channel::OneshotChannel<Output=jsonrpc::Response>) -> Result<Option<Vec<u8>>, Error> {
     let response = channel.fire(&jsonrpc).await?;
     let result = response.as_result::<String>()?;
-    result.map(|result| bytes_from_hex(result)).transpose()
+    result.map(bytes_from_hex).transpose()
 }
 
 async fn expect_json_response<D>(jsonrpc: JsonRpc, channel: &dyn channel::OneshotChannel<Output=jsonrpc::Response>) -> Result<Option<D>, Error>
@@ -117,6 +162,32 @@ where
     response.as_result::<D>()
 }
 
+// Decodes the results of notifications for this subscription and drops everything else. Without
+// a subscription id there is no telling them apart, so the subscribe fails.
+async fn expect_notifications<D>(jsonrpc: JsonRpc, channel: &dyn SubscriptionChannel<Item=JsonRpc>) -> Result<Subscriber<D>, Error>
+where
+    for <'de> D: serde::Deserialize<'de> + 'static
+{
+    let subscriber = channel.subscribe(&jsonrpc).await?;
+    let id = subscriber.id().map(ToString::to_string).ok_or(Error::MissingSubscriptionId)?;
+
+    let subscriber = subscriber.filter_map(move |notification| {
+        let notification = match notification {
+            Ok(notification) => notification,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let subscription = notification.params.get("subscription").map(channel::subscription_key);
+        if subscription.as_ref() != Some(&id) {
+            return None;
+        }
+
+        let result = notification.params.get("result").cloned().unwrap_or_default();
+        Some(serde_json::from_value::<D>(result).map_err(Error::from))
+    });
+    Ok(subscriber)
+}
+
 fn strip_hex(hex: &str) -> &str {
     match hex.starts_with("0x") {
         true => &hex[2..],
@@ -146,9 +217,10 @@ mod tests {
     use futures::StreamExt;
     use super::EthereumNetwork;
     use crate::network::NetworkOptions;
-    use crate::JsonRpc;
-    use crate::jsonrpc::Tag;
-    use crate::channel::SubscriptionChannel;
+    use crate::network::{LogFilter, PendingTransaction};
+    use crate::{Error, JsonRpc};
+    use crate::jsonrpc::{Response, Tag};
+    use crate::channel::{OneshotChannel, Subscriber, SubscriptionChannel};
     use crate::channel::{HttpChannel, WebsocketChannel};
 
     fn oneshot_channel() -> HttpChannel {
@@ -202,7 +274,7 @@ mod tests {
         let channel = oneshot_channel();
         let network = ethereum_network();
 
-        const MULTICALL2: &'static str = "0x5BA1e12693Dc8F9c48aAD8770482f4739bEeD696";
+        const MULTICALL2: &str = "0x5BA1e12693Dc8F9c48aAD8770482f4739bEeD696";
         let code = network.code(&channel ,MULTICALL2, Tag::Latest).await.unwrap();
         assert!(dbg!(code.unwrap().len()) > 0);
     }
@@ -212,7 +284,7 @@ mod tests {
         let channel = oneshot_channel();
         let network = ethereum_network();
 
-        const WETH: &'static str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
         let balance = network.balance(&channel, WETH, Tag::Latest).await.unwrap();
         assert!(dbg!(balance.unwrap()) > BigInt::zero());
     }
@@ -225,19 +297,67 @@ mod tests {
         let mut subscriber = network.subscribe(channel.as_ref(), "newHeads").await.unwrap();
 
         let item = subscriber.next().await;
-        assert_eq!(item.is_some(), true);
+        assert!(item.is_some());
         let result = item.unwrap();
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
         let response = result.unwrap();
         assert_eq!(response.method, "eth_subscription");
     }
 
+    // Answers 0x01 and rejects every other address, recording the size of each batch.
+    #[derive(Default)]
+    struct BalanceChannel {
+        batches: parking_lot::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl OneshotChannel for BalanceChannel {
+        type Output = Response;
+
+        async fn fire_batch(&self, batch: &[JsonRpc]) -> Result<Vec<Result<Self::Output, Error>>, Error> {
+            self.batches.lock().push(batch.len());
+            let mut results = Vec::with_capacity(batch.len());
+            for jsonrpc in batch {
+                results.push(self.fire(jsonrpc).await);
+            }
+            Ok(results)
+        }
+
+        async fn fire(&self, jsonrpc: &JsonRpc) -> Result<Self::Output, Error> {
+            let address = jsonrpc.params[0].as_str().unwrap();
+            let (result, error) = match address {
+                "0x01" => (Some(json!("0x10")), None),
+                _ => (None, Some(json!({ "code": -32602, "message": "invalid address" }))),
+            };
+            Ok(Response { id: jsonrpc.id.clone().unwrap(), result, error })
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_ethereum_balances() {
+        let network = ethereum_network();
+        let channel = BalanceChannel::default();
+        let balances = network.balances(&channel, &["0x01", "0x02"], Tag::Latest).await.unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].as_ref().unwrap(), &Some(BigInt::from(16)));
+        assert!(matches!(balances[1], Err(Error::JsonRpcError(_))));
+        assert_eq!(*channel.batches.lock(), vec![2]);
+
+        let network = ethereum_network().with_max_batch(2);
+        let channel = BalanceChannel::default();
+        let balances = network.balances(&channel, &["0x01", "0x02", "0x01", "0x01", "0x02"], Tag::Latest).await.unwrap();
+        assert_eq!(balances.len(), 5);
+        assert_eq!(balances[3].as_ref().unwrap(), &Some(BigInt::from(16)));
+        assert!(matches!(balances[4], Err(Error::JsonRpcError(_))));
+        assert_eq!(*channel.batches.lock(), vec![2, 2, 1]);
+    }
+
     #[tokio::test]
     async fn requests_ethereum_transaction_count() {
         let channel = oneshot_channel();
         let network = ethereum_network();
 
-        const WETH: &'static str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
         let transaction_count = network.transaction_count(&channel, WETH, Tag::Latest).await.unwrap();
         assert!(dbg!(transaction_count.unwrap()) > BigInt::zero());
     }
@@ -247,7 +367,7 @@ mod tests {
         let channel = oneshot_channel();
         let network = ethereum_network();
 
-        const WETH: &'static str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
         let response = network.call(
             &channel, 
             WETH,
@@ -256,4 +376,122 @@ mod tests {
         ).await.unwrap();
         assert_eq!(dbg!(response.unwrap().len()), 96);
     }
+
+    // Replays canned notifications, including one for another subscription, and records requests.
+    // Without an id the subscriber comes back the way a channel that doesn't track ids hands it out.
+    struct NotificationChannel {
+        results: Vec<serde_json::Value>,
+        requests: parking_lot::Mutex<Vec<JsonRpc>>,
+        anonymous: bool,
+    }
+
+    impl NotificationChannel {
+        fn new(results: Vec<serde_json::Value>) -> Self {
+            Self { results, requests: parking_lot::Mutex::new(Vec::new()), anonymous: false }
+        }
+    }
+
+    #[async_trait]
+    impl SubscriptionChannel for NotificationChannel {
+        type Item = JsonRpc;
+
+        async fn subscribe(&self, jsonrpc: &JsonRpc) -> Result<Subscriber<Self::Item>, Error> {
+            self.requests.lock().push(jsonrpc.clone());
+            if jsonrpc.params[0] == "bogus" {
+                return Err(Error::JsonRpcError(json!({ "code": -32602, "message": "unsupported" })));
+            }
+
+            let mut notifications = vec![json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xother", "result": "0x00" },
+            })];
+            notifications.extend(self.results.iter().map(|result| json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": { "subscription": "0xabc", "result": result },
+            })));
+
+            let notifications = notifications.into_iter()
+                .map(|notification| Ok(serde_json::from_value::<JsonRpc>(notification).unwrap()))
+                .collect::<Vec<_>>();
+            let (cancel, _) = futures::channel::oneshot::channel();
+            let subscriber = Subscriber::new(Box::new(futures::stream::iter(notifications)));
+            match self.anonymous {
+                true => Ok(subscriber),
+                false => Ok(subscriber.with_cancel("0xabc".to_string(), cancel)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribes_ethereum_new_heads() {
+        let header = json!({
+            "hash": "0x01",
+            "parentHash": "0x02",
+            "number": "0x10",
+            "timestamp": "0x64",
+            "miner": "0x03",
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "baseFeePerGas": "0x7",
+            "stateRoot": "0x04",
+            "transactionsRoot": "0x05",
+            "receiptsRoot": "0x06",
+            "logsBloom": "0x00",
+            "extraData": "0x",
+        });
+        let channel = NotificationChannel::new(vec![header, json!({ "number": "0x11" })]);
+        let network = ethereum_network();
+
+        let mut subscriber = network.subscribe_new_heads(&channel).await.unwrap();
+        let header = subscriber.next().await.unwrap().unwrap();
+        assert_eq!(header.number, BigInt::from(16));
+        assert_eq!(header.base_fee_per_gas, Some(BigInt::from(7)));
+        assert!(matches!(subscriber.next().await, Some(Err(Error::JsonformatError(_)))));
+        assert!(subscriber.next().await.is_none());
+        assert_eq!(channel.requests.lock()[0].params, json!(["newHeads"]));
+    }
+
+    #[tokio::test]
+    async fn subscribes_ethereum_logs() {
+        let log = json!({ "address": "0x01", "topics": ["0xaa"], "data": "0x02", "removed": true });
+        let channel = NotificationChannel::new(vec![log]);
+        let network = ethereum_network();
+
+        let filter = LogFilter::new().with_address("0x01").with_topic(0, "0xaa");
+        let mut subscriber = network.subscribe_logs(&channel, &filter).await.unwrap();
+        let log = subscriber.next().await.unwrap().unwrap();
+        assert_eq!(log.topics, vec![vec![0xaa]]);
+        assert_eq!(log.data, vec![0x02]);
+        assert!(log.removed);
+        assert_eq!(channel.requests.lock()[0].params, json!(["logs", { "address": ["0x01"], "topics": [["0xaa"]] }]));
+    }
+
+    #[tokio::test]
+    async fn subscribes_ethereum_pending_transactions() {
+        let channel = NotificationChannel::new(vec![json!("0xabcd")]);
+        let network = ethereum_network();
+
+        let mut subscriber = network.subscribe_pending_transactions(&channel, false).await.unwrap();
+        assert_eq!(subscriber.next().await.unwrap().unwrap(), PendingTransaction::Hash(vec![0xab, 0xcd]));
+        assert_eq!(subscriber.id(), Some("0xabc"));
+
+        network.subscribe_pending_transactions(&channel, true).await.unwrap();
+        let requests = channel.requests.lock();
+        assert_eq!(requests[0].params, json!(["newPendingTransactions"]));
+        assert_eq!(requests[1].params, json!(["newPendingTransactions", true]));
+    }
+
+    #[tokio::test]
+    async fn rejects_ethereum_subscribe() {
+        let channel = NotificationChannel::new(Vec::new());
+        let network = ethereum_network();
+        let result = network.subscribe(&channel, "bogus").await;
+        assert!(matches!(result, Err(Error::JsonRpcError(_))));
+
+        let channel = NotificationChannel { anonymous: true, ..NotificationChannel::new(vec![json!("0xabcd")]) };
+        let result = network.subscribe_pending_transactions(&channel, false).await;
+        assert!(matches!(result, Err(Error::MissingSubscriptionId)));
+    }
 }
@@ -1,5 +1,7 @@
 pub use eth::EthereumNetwork;
 pub use options::NetworkOptions;
+pub use types::{Header, Log, LogFilter, PendingTransaction, Transaction};
 
 pub mod eth;
 pub mod options;
+pub mod types;
@@ -0,0 +1,258 @@
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+
+// Block header as delivered by `newHeads`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    #[serde(with = "data")]
+    pub hash: Vec<u8>,
+    #[serde(with = "data")]
+    pub parent_hash: Vec<u8>,
+    #[serde(with = "quantity")]
+    pub number: BigInt,
+    #[serde(with = "quantity")]
+    pub timestamp: BigInt,
+    #[serde(with = "data")]
+    pub miner: Vec<u8>,
+    #[serde(with = "quantity")]
+    pub gas_limit: BigInt,
+    #[serde(with = "quantity")]
+    pub gas_used: BigInt,
+    #[serde(default, with = "optional_quantity")]
+    pub base_fee_per_gas: Option<BigInt>,
+    #[serde(with = "data")]
+    pub state_root: Vec<u8>,
+    #[serde(with = "data")]
+    pub transactions_root: Vec<u8>,
+    #[serde(with = "data")]
+    pub receipts_root: Vec<u8>,
+    #[serde(with = "data")]
+    pub logs_bloom: Vec<u8>,
+    #[serde(with = "data")]
+    pub extra_data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    #[serde(with = "data")]
+    pub address: Vec<u8>,
+    #[serde(with = "topics")]
+    pub topics: Vec<Vec<u8>>,
+    #[serde(with = "data")]
+    pub data: Vec<u8>,
+    #[serde(default, with = "optional_quantity")]
+    pub block_number: Option<BigInt>,
+    #[serde(default, with = "optional_data")]
+    pub block_hash: Option<Vec<u8>>,
+    #[serde(default, with = "optional_data")]
+    pub transaction_hash: Option<Vec<u8>>,
+    #[serde(default, with = "optional_quantity")]
+    pub transaction_index: Option<BigInt>,
+    #[serde(default, with = "optional_quantity")]
+    pub log_index: Option<BigInt>,
+    // Set when a reorg drops the block the log was emitted in.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    #[serde(with = "data")]
+    pub hash: Vec<u8>,
+    #[serde(with = "data")]
+    pub from: Vec<u8>,
+    #[serde(default, with = "optional_data")]
+    pub to: Option<Vec<u8>>,
+    #[serde(with = "quantity")]
+    pub nonce: BigInt,
+    #[serde(with = "quantity")]
+    pub value: BigInt,
+    #[serde(with = "quantity")]
+    pub gas: BigInt,
+    #[serde(default, with = "optional_quantity")]
+    pub gas_price: Option<BigInt>,
+    #[serde(default, with = "optional_quantity")]
+    pub max_fee_per_gas: Option<BigInt>,
+    #[serde(default, with = "optional_quantity")]
+    pub max_priority_fee_per_gas: Option<BigInt>,
+    #[serde(with = "data")]
+    pub input: Vec<u8>,
+}
+
+// `newPendingTransactions` sends hashes, or whole transactions when asked for them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum PendingTransaction {
+    Hash(#[serde(with = "data")] Vec<u8>),
+    Full(Box<Transaction>),
+}
+
+// Filter for `logs` subscriptions. Topics are positional; alternatives at the same position match
+// any of them, and positions left empty match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LogFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    address: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    topics: Vec<Option<Vec<String>>>,
+}
+
+impl LogFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_address<A>(mut self, address: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.address.push(address.into());
+        self
+    }
+
+    pub fn with_topic<T>(mut self, position: usize, topic: T) -> Self
+    where
+        T: Into<String>,
+    {
+        if self.topics.len() <= position {
+            self.topics.resize(position + 1, None);
+        }
+        self.topics[position].get_or_insert_with(Vec::new).push(topic.into());
+        self
+    }
+}
+
+fn strip_hex(hex: &str) -> &str {
+    hex.strip_prefix("0x").unwrap_or(hex)
+}
+
+mod quantity {
+    use num_bigint::BigInt;
+    use num_traits::Num;
+    use serde::{de, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        BigInt::from_str_radix(super::strip_hex(&hex), 16)
+            .map_err(|_| de::Error::custom(format!("invalid quantity {}", hex)))
+    }
+}
+
+mod optional_quantity {
+    use num_bigint::BigInt;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BigInt>, D::Error> {
+        #[derive(Deserialize)]
+        struct Quantity(#[serde(with = "super::quantity")] BigInt);
+
+        let quantity = Option::<Quantity>::deserialize(deserializer)?;
+        Ok(quantity.map(|Quantity(quantity)| quantity))
+    }
+}
+
+mod data {
+    use serde::{de, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex::decode(super::strip_hex(&hex))
+            .map_err(|_| de::Error::custom(format!("invalid data {}", hex)))
+    }
+}
+
+mod optional_data {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Data(#[serde(with = "super::data")] Vec<u8>);
+
+        let data = Option::<Data>::deserialize(deserializer)?;
+        Ok(data.map(|Data(data)| data))
+    }
+}
+
+mod topics {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Topic(#[serde(with = "super::data")] Vec<u8>);
+
+        let topics = Vec::<Topic>::deserialize(deserializer)?;
+        Ok(topics.into_iter().map(|Topic(topic)| topic).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        let filter = LogFilter::new()
+            .with_address("0x01")
+            .with_topic(0, "0xaa")
+            .with_topic(2, "0xbb")
+            .with_topic(2, "0xcc");
+
+        assert_eq!(serde_json::to_value(&filter).unwrap(), json!({
+            "address": ["0x01"],
+            "topics": [["0xaa"], null, ["0xbb", "0xcc"]],
+        }));
+        assert_eq!(serde_json::to_value(LogFilter::new()).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let log = serde_json::from_value::<Log>(json!({
+            "address": "0x0102",
+            "topics": ["0xaa", "0xbb"],
+            "data": "0x",
+            "blockNumber": "0x10",
+            "blockHash": null,
+            "logIndex": "0x1",
+        })).unwrap();
+
+        assert_eq!(log.address, vec![1, 2]);
+        assert_eq!(log.topics, vec![vec![0xaa], vec![0xbb]]);
+        assert!(log.data.is_empty());
+        assert_eq!(log.block_number, Some(BigInt::from(16)));
+        assert_eq!(log.block_hash, None);
+        assert_eq!(log.transaction_hash, None);
+        assert_eq!(log.log_index, Some(BigInt::from(1)));
+        assert!(!log.removed);
+
+        assert!(serde_json::from_value::<Log>(json!({ "address": "0xzz", "topics": [], "data": "0x" })).is_err());
+    }
+
+    #[test]
+    fn test_decode_pending_transaction() {
+        let hash = serde_json::from_value::<PendingTransaction>(json!("0xabcd")).unwrap();
+        assert_eq!(hash, PendingTransaction::Hash(vec![0xab, 0xcd]));
+
+        let full = serde_json::from_value::<PendingTransaction>(json!({
+            "hash": "0xabcd",
+            "from": "0x01",
+            "to": null,
+            "nonce": "0x0",
+            "value": "0xde0b6b3a7640000",
+            "gas": "0x5208",
+            "maxFeePerGas": "0x3b9aca00",
+            "input": "0x",
+        })).unwrap();
+        match full {
+            PendingTransaction::Full(transaction) => {
+                assert_eq!(transaction.to, None);
+                assert_eq!(transaction.value, BigInt::from(10_u64.pow(18)));
+                assert_eq!(transaction.gas_price, None);
+                assert_eq!(transaction.max_fee_per_gas, Some(BigInt::from(1_000_000_000)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}